@@ -0,0 +1,54 @@
+use anchor_client::solana_sdk::{native_token::LAMPORTS_PER_SOL, signer::Signer};
+use anyhow::{bail, Result};
+use clients::king_of_the_hill::{self as koth, GameStatus};
+use std::env;
+
+const USAGE: &str = "Usage: koth <create LAMPORTS | bid LAMPORTS | status>";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let payer = clients::load_wallet()?;
+    let program = clients::localnet_program(payer.clone(), koth::PROGRAM_ID)?;
+
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["create", lamports] => {
+            let generation = koth::fetch_next_generation(&program).await?;
+            let instruction = koth::initialize(&payer.pubkey(), lamports.parse()?, generation);
+            let signature = program.request().instruction(instruction).send().await?;
+            println!("Game created: {}", koth::game_state_address());
+            println!("Transaction Signature: {}", signature);
+        }
+        ["bid", lamports] => {
+            // The instruction needs the current king and admin of the game
+            let game = koth::fetch_game_state(&program).await?;
+            let instruction = koth::become_king(&game, &payer.pubkey(), lamports.parse()?, None);
+            let signature = program.request().instruction(instruction).send().await?;
+            println!("You are the king of the hill!");
+            println!("Transaction Signature: {}", signature);
+        }
+        ["status"] => {
+            let game = koth::fetch_game_state(&program).await?;
+            let status = match game.status {
+                GameStatus::Active => "active",
+                GameStatus::Paused => "paused",
+                GameStatus::Finished => "finished",
+            };
+
+            println!("Game: {}", koth::game_state_address());
+            println!("Status: {}", status);
+            println!("King: {}", game.king);
+            println!(
+                "Prize: {} lamports ({} SOL)",
+                game.prize,
+                game.prize as f64 / LAMPORTS_PER_SOL as f64
+            );
+            println!("Crowned at: {}", game.crowned_at);
+            println!("Admin: {}", game.admin);
+        }
+        _ => bail!(USAGE),
+    }
+
+    Ok(())
+}
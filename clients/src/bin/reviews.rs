@@ -0,0 +1,151 @@
+use anchor_client::{
+    solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signer::Signer},
+    Cluster,
+};
+use anyhow::{anyhow, bail, Result};
+use clients::movie_review::{self as reviews, Genre, MovieReviewData, ReviewInput};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::env;
+
+const USAGE: &str = "Usage: reviews <add TITLE RATING GENRE YEAR DESCRIPTION [TAG...] \
+                     | update TITLE RATING GENRE YEAR DESCRIPTION [TAG...] \
+                     | delete TITLE | list [REVIEWER] | show TITLE [REVIEWER]>";
+
+// Names accepted on the command line, in the order of the Genre variants
+const GENRES: [(&str, Genre); 11] = [
+    ("action", Genre::Action),
+    ("animation", Genre::Animation),
+    ("comedy", Genre::Comedy),
+    ("documentary", Genre::Documentary),
+    ("drama", Genre::Drama),
+    ("fantasy", Genre::Fantasy),
+    ("horror", Genre::Horror),
+    ("romance", Genre::Romance),
+    ("science-fiction", Genre::ScienceFiction),
+    ("thriller", Genre::Thriller),
+    ("other", Genre::Other),
+];
+
+fn parse_genre(name: &str) -> Result<Genre> {
+    GENRES
+        .iter()
+        .find(|(genre_name, _)| genre_name.eq_ignore_ascii_case(name))
+        .map(|(_, genre)| *genre)
+        .ok_or_else(|| anyhow!("Unknown genre {name}"))
+}
+
+fn genre_name(genre: Genre) -> &'static str {
+    GENRES
+        .iter()
+        .find(|(_, g)| *g == genre)
+        .map(|(name, _)| *name)
+        .unwrap_or("unknown")
+}
+
+// Reads the arguments shared by add and update
+fn parse_review(args: &[&str]) -> Result<ReviewInput> {
+    let [title, rating, genre, year, description, tags @ ..] = args else {
+        bail!(USAGE);
+    };
+
+    Ok(ReviewInput {
+        title: title.to_string(),
+        description: description.to_string(),
+        rating: rating.parse()?,
+        genre: parse_genre(genre)?,
+        release_year: year.parse()?,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+    })
+}
+
+fn print_review(address: &Pubkey, review: &MovieReviewData) {
+    println!("{} ({})", review.title, review.release_year);
+    println!("  Review: {}", address);
+    println!("  Reviewer: {}", review.reviewer);
+    println!("  Rating: {}/10", review.rating);
+    println!("  Genre: {}", genre_name(review.genre));
+    if !review.tags.is_empty() {
+        println!("  Tags: {}", review.tags.join(", "));
+    }
+    println!("  {}", review.description);
+    println!(
+        "  Likes: {}, edited {} times, last on {}",
+        review.like_count, review.edit_count, review.updated_at
+    );
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let payer = clients::load_wallet()?;
+    let program = clients::localnet_program(payer.clone(), reviews::PROGRAM_ID)?;
+    // Listing needs server-side filters, which go through the RPC client directly
+    let rpc = RpcClient::new_with_commitment(
+        Cluster::Localnet.url().to_string(),
+        CommitmentConfig::confirmed(),
+    );
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["add", review @ ..] => {
+            let review = parse_review(review)?;
+            let instruction = reviews::add_movie_review(&payer.pubkey(), &review);
+            let signature = program.request().instruction(instruction).send().await?;
+            println!(
+                "Review added: {}",
+                reviews::review_address(&review.title, &payer.pubkey())
+            );
+            println!("Transaction Signature: {}", signature);
+        }
+        ["update", review @ ..] => {
+            let review = parse_review(review)?;
+            let instruction = reviews::update_movie_review(&payer.pubkey(), &review);
+            let signature = program.request().instruction(instruction).send().await?;
+            println!("Review updated");
+            println!("Transaction Signature: {}", signature);
+        }
+        ["delete", title] => {
+            let instruction = reviews::delete_movie_review(&payer.pubkey(), title);
+            let signature = program.request().instruction(instruction).send().await?;
+            println!("Review deleted");
+            println!("Transaction Signature: {}", signature);
+        }
+        ["list"] => {
+            for (address, review) in reviews::list_reviews(&rpc, None).await? {
+                print_review(&address, &review);
+            }
+        }
+        ["list", reviewer] => {
+            let reviewer: Pubkey = reviewer.parse()?;
+            for (address, review) in reviews::list_reviews(&rpc, Some(&reviewer)).await? {
+                print_review(&address, &review);
+            }
+        }
+        ["show", title, reviewer @ ..] => {
+            let reviewer = match reviewer {
+                [] => payer.pubkey(),
+                [reviewer] => reviewer.parse()?,
+                _ => bail!(USAGE),
+            };
+            let address = reviews::review_address(title, &reviewer);
+            let review = reviews::fetch_review(&program, &address).await?;
+            print_review(&address, &review);
+
+            let movie = reviews::fetch_movie(&program, title).await?;
+            if let Some(average) = movie.average_rating() {
+                println!(
+                    "Average rating: {:.1}/10 from {} reviews",
+                    average, movie.review_count
+                );
+            }
+        }
+        _ => bail!(USAGE),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,318 @@
+use anchor_client::{
+    anchor_lang::{system_program, InstructionData, ToAccountMetas},
+    solana_sdk::{instruction::Instruction, pubkey::Pubkey, signer::Signer},
+    ClientError, Program,
+};
+use anyhow::Result;
+use king_of_the_hill::{accounts, instruction};
+use std::ops::Deref;
+
+// Re-exported so users of this module don't need to depend on the program crate
+pub use king_of_the_hill::{GameCounter, GameRules, GameState, GameStatus, ID as PROGRAM_ID};
+
+// Address of the lamport game state. There is a single lamport game per program.
+pub fn game_state_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"game_state"], &PROGRAM_ID).0
+}
+
+// Address of the PDA that holds the lamports of the lamport game
+pub fn prize_pool_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"prize_pool"], &PROGRAM_ID).0
+}
+
+pub fn bid_history_address(game_state: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"bid_history", game_state.as_ref()], &PROGRAM_ID).0
+}
+
+// Address of the counter that numbers the games created at `game_state`
+pub fn game_counter_address(game_state: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"game_counter", game_state.as_ref()], &PROGRAM_ID).0
+}
+
+// Stats of `player` in the game with the given generation, see GameState::generation
+pub fn player_stats_address(game_state: &Pubkey, generation: u64, player: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"player_stats",
+            game_state.as_ref(),
+            &generation.to_le_bytes(),
+            player.as_ref(),
+        ],
+        &PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn referrer_balance_address(referrer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"referrer", referrer.as_ref()], &PROGRAM_ID).0
+}
+
+// Address of the state of the token game played with `mint`. There is one game per mint.
+pub fn token_game_state_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"game_state", mint.as_ref()], &PROGRAM_ID).0
+}
+
+// Address of the token account that holds the stake of the token game played with `mint`
+pub fn token_prize_pool_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"prize_pool", mint.as_ref()], &PROGRAM_ID).0
+}
+
+// Builds an instruction that creates the lamport game with `initial_king` as its first
+// king, admin and creator. `generation` is the one the new game gets, see
+// fetch_next_generation.
+pub fn initialize(initial_king: &Pubkey, initial_prize: u64, generation: u64) -> Instruction {
+    let game_state = game_state_address();
+
+    // The accounts and instruction modules are generated by Anchor in the program crate.
+    // accounts::Initialize holds the address of every account the instruction expects and
+    // instruction::Initialize serializes the arguments after the instruction discriminator.
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::Initialize {
+            game_state,
+            initial_king: *initial_king,
+            prize_pool: prize_pool_address(),
+            bid_history: bid_history_address(&game_state),
+            game_counter: game_counter_address(&game_state),
+            initial_king_stats: player_stats_address(&game_state, generation, initial_king),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Initialize { initial_prize }.data(),
+    }
+}
+
+// Builds an instruction that makes `payer` the new king of the lamport game. `game` must
+// be the current state of the game, since the instruction refunds its king and pays the
+// house fee to its admin.
+pub fn become_king(
+    game: &GameState,
+    payer: &Pubkey,
+    new_prize: u64,
+    referrer: Option<&Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: become_king_accounts(game, payer, referrer).to_account_metas(None),
+        data: instruction::BecomeKing { new_prize }.data(),
+    }
+}
+
+// The accounts of become_king, for callers that build the instruction themselves
+pub fn become_king_accounts(
+    game: &GameState,
+    payer: &Pubkey,
+    referrer: Option<&Pubkey>,
+) -> accounts::BecomeKing {
+    let game_state = game_state_address();
+
+    accounts::BecomeKing {
+        game_state,
+        king: game.king,
+        admin: game.admin,
+        payer: *payer,
+        prize_pool: prize_pool_address(),
+        bid_history: bid_history_address(&game_state),
+        king_stats: player_stats_address(&game_state, game.generation, &game.king),
+        payer_stats: player_stats_address(&game_state, game.generation, payer),
+        referrer_balance: referrer.map(referrer_balance_address),
+        system_program: system_program::ID,
+    }
+}
+
+// Refunds the king of the lamport game and finishes it. Signed by the admin of `game`.
+pub fn emergency_refund(game: &GameState) -> Instruction {
+    let game_state = game_state_address();
+
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::EmergencyRefund {
+            game_state,
+            admin: game.admin,
+            king: game.king,
+            prize_pool: prize_pool_address(),
+            king_stats: player_stats_address(&game_state, game.generation, &game.king),
+        }
+        .to_account_metas(None),
+        data: instruction::EmergencyRefund {}.data(),
+    }
+}
+
+// Closes the finished lamport game and returns its rent to its creator
+pub fn close_game(game: &GameState) -> Instruction {
+    let game_state = game_state_address();
+
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::CloseGame {
+            game_state,
+            creator: game.creator,
+            prize_pool: prize_pool_address(),
+            bid_history: bid_history_address(&game_state),
+        }
+        .to_account_metas(None),
+        data: instruction::CloseGame {}.data(),
+    }
+}
+
+pub fn set_fees(admin: &Pubkey, house_fee_bps: u16, referral_share_bps: u16) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::SetFees {
+            game_state: game_state_address(),
+            admin: *admin,
+        }
+        .to_account_metas(None),
+        data: instruction::SetFees {
+            house_fee_bps,
+            referral_share_bps,
+        }
+        .data(),
+    }
+}
+
+// Sets the bid rules of the game at `game_state`, which can be the lamport or a token game
+pub fn set_rules(game_state: &Pubkey, admin: &Pubkey, rules: GameRules) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::AdminOnly {
+            game_state: *game_state,
+            admin: *admin,
+        }
+        .to_account_metas(None),
+        data: instruction::SetRules { rules }.data(),
+    }
+}
+
+pub fn register_referrer(referrer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::RegisterReferrer {
+            referrer_balance: referrer_balance_address(referrer),
+            referrer: *referrer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::RegisterReferrer {}.data(),
+    }
+}
+
+pub fn claim_referral(referrer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::ClaimReferral {
+            referrer_balance: referrer_balance_address(referrer),
+            referrer: *referrer,
+        }
+        .to_account_metas(None),
+        data: instruction::ClaimReferral {}.data(),
+    }
+}
+
+// Builds an instruction that creates the token game of `mint`, with `initial_king` staking
+// `initial_prize` from `initial_king_token_account`. `token_program` is the program that
+// owns the mint, Token or Token-2022.
+pub fn initialize_token_game(
+    token_program: &Pubkey,
+    mint: &Pubkey,
+    initial_king: &Pubkey,
+    initial_king_token_account: &Pubkey,
+    initial_prize: u64,
+    generation: u64,
+) -> Instruction {
+    let game_state = token_game_state_address(mint);
+
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::InitializeTokenGame {
+            game_state,
+            initial_king: *initial_king,
+            mint: *mint,
+            initial_king_token_account: *initial_king_token_account,
+            prize_pool: token_prize_pool_address(mint),
+            bid_history: bid_history_address(&game_state),
+            game_counter: game_counter_address(&game_state),
+            initial_king_stats: player_stats_address(&game_state, generation, initial_king),
+            token_program: *token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeTokenGame { initial_prize }.data(),
+    }
+}
+
+// Builds an instruction that makes `payer` the new king of the token game `game`, paying
+// from `payer_token_account` and refunding the king into `king_token_account`
+pub fn become_king_token(
+    game: &GameState,
+    token_program: &Pubkey,
+    payer: &Pubkey,
+    payer_token_account: &Pubkey,
+    king_token_account: &Pubkey,
+    new_prize: u64,
+) -> Instruction {
+    let game_state = token_game_state_address(&game.mint);
+
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::BecomeKingToken {
+            game_state,
+            payer: *payer,
+            mint: game.mint,
+            payer_token_account: *payer_token_account,
+            king_token_account: *king_token_account,
+            prize_pool: token_prize_pool_address(&game.mint),
+            bid_history: bid_history_address(&game_state),
+            king_stats: player_stats_address(&game_state, game.generation, &game.king),
+            payer_stats: player_stats_address(&game_state, game.generation, payer),
+            token_program: *token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::BecomeKingToken { new_prize }.data(),
+    }
+}
+
+// Refunds the king of the token game `game` into `king_token_account` and finishes the game
+pub fn emergency_refund_token(
+    game: &GameState,
+    token_program: &Pubkey,
+    king_token_account: &Pubkey,
+) -> Instruction {
+    let game_state = token_game_state_address(&game.mint);
+
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::EmergencyRefundToken {
+            game_state,
+            admin: game.admin,
+            mint: game.mint,
+            king_token_account: *king_token_account,
+            prize_pool: token_prize_pool_address(&game.mint),
+            king_stats: player_stats_address(&game_state, game.generation, &game.king),
+            token_program: *token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::EmergencyRefundToken {}.data(),
+    }
+}
+
+// Fetches the lamport game state and decodes it, checking the account discriminator
+pub async fn fetch_game_state<C: Deref<Target = impl Signer> + Clone>(
+    program: &Program<C>,
+) -> Result<GameState> {
+    Ok(program.account::<GameState>(game_state_address()).await?)
+}
+
+// Generation of the next lamport game, which is the number of lamport games created so far.
+// The counter is created with the first game, so it doesn't exist before that.
+pub async fn fetch_next_generation<C: Deref<Target = impl Signer> + Clone>(
+    program: &Program<C>,
+) -> Result<u64> {
+    let counter_address = game_counter_address(&game_state_address());
+    match program.account::<GameCounter>(counter_address).await {
+        Ok(counter) => Ok(counter.games_created),
+        Err(ClientError::AccountNotFound) => Ok(0),
+        Err(err) => Err(err.into()),
+    }
+}
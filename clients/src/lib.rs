@@ -0,0 +1,29 @@
+// Typed clients for the programs in this workspace
+pub mod king_of_the_hill;
+pub mod movie_review;
+
+use anchor_client::{
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair},
+    },
+    Client, Cluster, Program,
+};
+use anyhow::{anyhow, Result};
+use std::{env, sync::Arc};
+
+// Loads the same wallet as Anchor, falling back to the default Solana CLI keypair
+pub fn load_wallet() -> Result<Arc<Keypair>> {
+    let wallet_path = env::var("ANCHOR_WALLET")
+        .or_else(|_| env::var("HOME").map(|home| format!("{home}/.config/solana/id.json")))?;
+    let wallet = read_keypair_file(&wallet_path)
+        .map_err(|err| anyhow!("Failed to read wallet {wallet_path}: {err}"))?;
+    Ok(Arc::new(wallet))
+}
+
+// Client for `program_id` on the local validator, with `payer` signing and paying the fees
+pub fn localnet_program(payer: Arc<Keypair>, program_id: Pubkey) -> Result<Program<Arc<Keypair>>> {
+    let client = Client::new_with_options(Cluster::Localnet, payer, CommitmentConfig::confirmed());
+    Ok(client.program(program_id)?)
+}
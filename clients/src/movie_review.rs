@@ -0,0 +1,462 @@
+use anchor_client::{
+    anchor_lang::{
+        system_program, AccountDeserialize, Discriminator, InstructionData, ToAccountMetas,
+    },
+    solana_sdk::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        signer::Signer,
+    },
+    Program,
+};
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address_with_program_id},
+    token,
+};
+use anyhow::Result;
+use movie_review::{accounts, instruction, title_seed};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::RpcProgramAccountsConfig,
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk_ids::bpf_loader_upgradeable;
+use std::ops::Deref;
+
+// Re-exported so users of this module don't need to depend on the program crate
+pub use movie_review::{Genre, Movie, MovieReviewData, ReviewStatus, ID as PROGRAM_ID};
+
+// Everything a reviewer writes in a review, shared by the add and update instructions
+#[derive(Clone)]
+pub struct ReviewInput {
+    pub title: String,
+    pub description: String,
+    pub rating: u8,
+    pub genre: Genre,
+    pub release_year: u16,
+    pub tags: Vec<String>,
+}
+
+// Address of the review `reviewer` wrote for `title`. Titles are lowercased and hashed by
+// `title_seed`, so titles that only differ in case share a review. Titles with surrounding
+// whitespace are rejected by the program.
+pub fn review_address(title: &str, reviewer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[title_seed(title).as_ref(), reviewer.as_ref()],
+        &PROGRAM_ID,
+    )
+    .0
+}
+
+// Address of the rating aggregate shared by every review of `title`
+pub fn movie_address(title: &str) -> Pubkey {
+    Pubkey::find_program_address(&[b"movie", title_seed(title).as_ref()], &PROGRAM_ID).0
+}
+
+pub fn profile_address(reviewer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"profile", reviewer.as_ref()], &PROGRAM_ID).0
+}
+
+// Marks that `reviewer` has been rewarded for reviewing `title`
+pub fn reward_receipt_address(title: &str, reviewer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"rewarded", title_seed(title).as_ref(), reviewer.as_ref()],
+        &PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn reward_config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"reward_config"], &PROGRAM_ID).0
+}
+
+pub fn reward_mint_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"reward_mint"], &PROGRAM_ID).0
+}
+
+pub fn mint_authority_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"mint_authority"], &PROGRAM_ID).0
+}
+
+// Address of the moderation config, which holds the admin and the moderator list
+pub fn config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &PROGRAM_ID).0
+}
+
+// Address of the account where the upgradeable loader records the program's upgrade
+// authority, who is the only one allowed to create the reward mint and the config
+pub fn program_data_address() -> Pubkey {
+    Pubkey::find_program_address(&[PROGRAM_ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+// Marks that `voter` likes `review`
+pub fn like_address(review: &Pubkey, voter: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"like", review.as_ref(), voter.as_ref()], &PROGRAM_ID).0
+}
+
+// Marks that `reporter` has reported `review`
+pub fn report_address(review: &Pubkey, reporter: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"report", review.as_ref(), reporter.as_ref()],
+        &PROGRAM_ID,
+    )
+    .0
+}
+
+// Address of the counter that numbers the comments of `review`
+pub fn comment_counter_address(review: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"comment_counter", review.as_ref()], &PROGRAM_ID).0
+}
+
+// Address of the comment of `review` with the given index, see CommentCounter
+pub fn comment_address(review: &Pubkey, index: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"comment", review.as_ref(), &index.to_le_bytes()],
+        &PROGRAM_ID,
+    )
+    .0
+}
+
+// Associated token account of `reviewer` that receives the review rewards
+pub fn reviewer_token_account(reviewer: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(reviewer, &reward_mint_address(), &token::ID)
+}
+
+// Builds an instruction that creates the reward mint with the Token program and makes
+// `admin` the admin of the rewards. Only the upgrade authority of the program can send it.
+pub fn initialize_reward_mint(admin: &Pubkey, reward_amount: u64) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::InitializeRewardMint {
+            reward_config: reward_config_address(),
+            reward_mint: reward_mint_address(),
+            mint_authority: mint_authority_address(),
+            program_data: program_data_address(),
+            admin: *admin,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeRewardMint { reward_amount }.data(),
+    }
+}
+
+// Builds an instruction that creates the moderation config with `admin` as its admin. Only
+// the upgrade authority of the program can send it.
+pub fn initialize_config(admin: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::InitializeConfig {
+            config: config_address(),
+            program_data: program_data_address(),
+            admin: *admin,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeConfig {}.data(),
+    }
+}
+
+// Builds an instruction that adds a review and mints the review reward to the reviewer's
+// associated token account, unless they were already rewarded for this title. Assumes the
+// reward mint was created with the Token program.
+pub fn add_movie_review(reviewer: &Pubkey, review: &ReviewInput) -> Instruction {
+    // The accounts and instruction modules are generated by Anchor in the program crate
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::AddMovieReview {
+            movie_review: review_address(&review.title, reviewer),
+            movie: movie_address(&review.title),
+            reward_receipt: reward_receipt_address(&review.title, reviewer),
+            reward_config: reward_config_address(),
+            reward_mint: reward_mint_address(),
+            mint_authority: mint_authority_address(),
+            reviewer_token_account: reviewer_token_account(reviewer),
+            reviewer_profile: profile_address(reviewer),
+            reviewer: *reviewer,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::AddMovieReview {
+            title: review.title.clone(),
+            description: review.description.clone(),
+            rating: review.rating,
+            genre: review.genre,
+            release_year: review.release_year,
+            tags: review.tags.clone(),
+        }
+        .data(),
+    }
+}
+
+// Builds an instruction that replaces the contents of an existing review. The title selects
+// the review and can only change its capitalization.
+pub fn update_movie_review(reviewer: &Pubkey, review: &ReviewInput) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::UpdateMovieReview {
+            movie_review: review_address(&review.title, reviewer),
+            movie: movie_address(&review.title),
+            reviewer: *reviewer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateMovieReview {
+            title: review.title.clone(),
+            description: review.description.clone(),
+            rating: review.rating,
+            genre: review.genre,
+            release_year: review.release_year,
+            tags: review.tags.clone(),
+        }
+        .data(),
+    }
+}
+
+pub fn delete_movie_review(reviewer: &Pubkey, title: &str) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::DeleteMovieReview {
+            movie_review: review_address(title, reviewer),
+            movie: movie_address(title),
+            reviewer_profile: profile_address(reviewer),
+            reviewer: *reviewer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::DeleteMovieReview {
+            title: title.to_string(),
+        }
+        .data(),
+    }
+}
+
+// Builds an instruction that moves the review of `old_title` to `new_title`. Reviews with
+// likes or comments can't be renamed.
+pub fn rename_movie_review(reviewer: &Pubkey, old_title: &str, new_title: &str) -> Instruction {
+    let old_review = review_address(old_title, reviewer);
+
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::RenameMovieReview {
+            old_review,
+            new_review: review_address(new_title, reviewer),
+            old_movie: movie_address(old_title),
+            new_movie: movie_address(new_title),
+            new_reward_receipt: reward_receipt_address(new_title, reviewer),
+            old_comment_counter: comment_counter_address(&old_review),
+            reviewer: *reviewer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::RenameMovieReview {
+            old_title: old_title.to_string(),
+            new_title: new_title.to_string(),
+        }
+        .data(),
+    }
+}
+
+pub fn update_profile(authority: &Pubkey, display_name: &str) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::UpdateProfile {
+            reviewer_profile: profile_address(authority),
+            authority: *authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateProfile {
+            display_name: display_name.to_string(),
+        }
+        .data(),
+    }
+}
+
+// Builds an instruction that adds a comment to `review`. `index` must be the current count
+// of the review's comment counter, or 0 for its first comment.
+pub fn add_comment(author: &Pubkey, review: &Pubkey, index: u64, body: &str) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::AddComment {
+            movie_review: *review,
+            comment_counter: comment_counter_address(review),
+            comment: comment_address(review, index),
+            author: *author,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::AddComment {
+            body: body.to_string(),
+        }
+        .data(),
+    }
+}
+
+pub fn delete_comment(author: &Pubkey, review: &Pubkey, index: u64) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::DeleteComment {
+            comment: comment_address(review, index),
+            comment_counter: comment_counter_address(review),
+            author: *author,
+        }
+        .to_account_metas(None),
+        data: instruction::DeleteComment {}.data(),
+    }
+}
+
+// `reviewer` is the author of the review, whose reputation the like counts towards
+pub fn like_review(voter: &Pubkey, review: &Pubkey, reviewer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::LikeReview {
+            movie_review: *review,
+            like: like_address(review, voter),
+            reviewer_profile: profile_address(reviewer),
+            voter: *voter,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::LikeReview {}.data(),
+    }
+}
+
+pub fn unlike_review(voter: &Pubkey, review: &Pubkey, reviewer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::UnlikeReview {
+            movie_review: *review,
+            like: like_address(review, voter),
+            reviewer_profile: profile_address(reviewer),
+            voter: *voter,
+        }
+        .to_account_metas(None),
+        data: instruction::UnlikeReview {}.data(),
+    }
+}
+
+pub fn report_review(reporter: &Pubkey, review: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts::ReportReview {
+            movie_review: *review,
+            report: report_address(review, reporter),
+            reporter: *reporter,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::ReportReview {}.data(),
+    }
+}
+
+pub fn hide_review(moderator: &Pubkey, review: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: moderate_review_accounts(moderator, review),
+        data: instruction::HideReview {}.data(),
+    }
+}
+
+pub fn unhide_review(moderator: &Pubkey, review: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: moderate_review_accounts(moderator, review),
+        data: instruction::UnhideReview {}.data(),
+    }
+}
+
+fn moderate_review_accounts(moderator: &Pubkey, review: &Pubkey) -> Vec<AccountMeta> {
+    accounts::ModerateReview {
+        movie_review: *review,
+        config: config_address(),
+        moderator: *moderator,
+    }
+    .to_account_metas(None)
+}
+
+pub fn add_moderator(admin: &Pubkey, moderator: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: manage_moderators_accounts(admin),
+        data: instruction::AddModerator {
+            moderator: *moderator,
+        }
+        .data(),
+    }
+}
+
+pub fn remove_moderator(admin: &Pubkey, moderator: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: manage_moderators_accounts(admin),
+        data: instruction::RemoveModerator {
+            moderator: *moderator,
+        }
+        .data(),
+    }
+}
+
+fn manage_moderators_accounts(admin: &Pubkey) -> Vec<AccountMeta> {
+    accounts::ManageModerators {
+        config: config_address(),
+        admin: *admin,
+    }
+    .to_account_metas(None)
+}
+
+// Fetches a review and decodes it, checking the account discriminator
+pub async fn fetch_review<C: Deref<Target = impl Signer> + Clone>(
+    program: &Program<C>,
+    address: &Pubkey,
+) -> Result<MovieReviewData> {
+    Ok(program.account::<MovieReviewData>(*address).await?)
+}
+
+pub async fn fetch_movie<C: Deref<Target = impl Signer> + Clone>(
+    program: &Program<C>,
+    title: &str,
+) -> Result<Movie> {
+    Ok(program.account::<Movie>(movie_address(title)).await?)
+}
+
+// Lists the reviews that clients should show, optionally only those of one reviewer. The
+// filtering happens on the RPC node: the discriminator filter keeps only review accounts and
+// the reviewer filter compares the reviewer field, which is at a fixed offset in every
+// review. Reviews hidden by a moderator are left out.
+pub async fn list_reviews(
+    rpc: &RpcClient,
+    reviewer: Option<&Pubkey>,
+) -> Result<Vec<(Pubkey, MovieReviewData)>> {
+    let mut filters = vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        0,
+        MovieReviewData::DISCRIMINATOR,
+    ))];
+    if let Some(reviewer) = reviewer {
+        filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            MovieReviewData::REVIEWER_OFFSET,
+            reviewer.as_ref(),
+        )));
+    }
+
+    let accounts = rpc
+        .get_program_accounts_with_config(
+            &PROGRAM_ID,
+            RpcProgramAccountsConfig {
+                filters: Some(filters),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    let mut reviews = Vec::with_capacity(accounts.len());
+    for (address, account) in accounts {
+        let review = MovieReviewData::try_deserialize(&mut account.data.as_slice())?;
+        if !review.is_hidden() {
+            reviews.push((address, review));
+        }
+    }
+    Ok(reviews)
+}
@@ -1,6 +1,10 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::program::invoke;
 use anchor_lang::solana_program::system_instruction;
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
 
 declare_id!("iALpvVQS1CoqrFuoHopycSv8tUCZRu75dZbfWBYj9ny");
 
@@ -16,8 +20,43 @@ pub mod king_of_the_hill {
 
         // Sets initial king from Initialize as the first king
         game_state.king = ctx.accounts.initial_king.key();
+        // The creator of the game is its admin and can pause it or refund the king
+        game_state.admin = ctx.accounts.initial_king.key();
+        // The creator paid the rent for the game accounts and gets it back when the game
+        // is closed
+        game_state.creator = ctx.accounts.initial_king.key();
+        // Stores the canonical bumps Anchor found while creating the PDAs, so later
+        // instructions can verify the addresses without searching for the bumps again.
+        game_state.bump = ctx.bumps.game_state;
+        game_state.prize_pool_bump = ctx.bumps.prize_pool;
         // Records the initial prize amount in the game state
         game_state.prize = initial_prize;
+        // Starts the reign of the initial king. The Clock sysvar is read directly with
+        // Clock::get(), so it doesn't need to be passed in as an account.
+        let clock = Clock::get()?;
+        let now = clock.unix_timestamp;
+        game_state.crowned_at = now;
+        // New games start with the default rules, the admin can change them with set_rules
+        game_state.rules = GameRules::default();
+        // Player stats are seeded with the generation of the game, so a game created after
+        // a previous one at this address was closed starts with fresh stats.
+        let game_counter = &mut ctx.accounts.game_counter;
+        game_state.generation = game_counter.games_created;
+        game_counter.games_created = game_counter
+            .games_created
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        // The first bid of the game is the initial prize
+        let game_key = game_state.key();
+        ctx.accounts.bid_history.game = game_key;
+        ctx.accounts.bid_history.push(game_state.king, initial_prize, now);
+        ctx.accounts.initial_king_stats.record_bid(
+            game_key,
+            game_state.king,
+            initial_prize,
+            clock.slot,
+        )?;
 
         // Call the System Program to prepare a transfer instruction
         let transfer_instruction = system_instruction::transfer(
@@ -39,11 +78,31 @@ pub mod king_of_the_hill {
                 ctx.accounts.system_program.to_account_info(), // system program
             ],
         )?; // Will propagate an error if the instruction fails to execute
+        check_prize_pool_invariant(&ctx.accounts.prize_pool, initial_prize)?;
+
+        // emit! serializes the event and writes it to the transaction logs, where clients
+        // can decode it with the program IDL instead of diffing account state.
+        emit!(GameCreated {
+            game: game_key,
+            mint: game_state.mint,
+            king: game_state.king,
+            prize: initial_prize,
+            timestamp: now,
+        });
 
         Ok(())
     }
 
     pub fn become_king(ctx: Context<BecomeKing>, new_prize: u64) -> Result<()> {
+        // Bids are only accepted while the game is neither paused nor finished
+        ctx.accounts.game_state.require_active()?;
+        // Enforces the game rules on the bidder before any funds move
+        let clock = Clock::get()?;
+        ctx.accounts.game_state.check_bid_rules(
+            ctx.accounts.payer.key(),
+            &ctx.accounts.payer_stats,
+            clock.slot,
+        )?;
         // Ensures that the new prize is bigger than the current prize
         require!(
             new_prize > ctx.accounts.game_state.prize,
@@ -71,8 +130,77 @@ pub mod king_of_the_hill {
         // Cross-program invocations to the system program (with system_instruction::)
         // are not needed because the prize_pool PDA is owned by this program, so it can 
         // directly modify its lamports.
-        ctx.accounts.prize_pool.sub_lamports(ctx.accounts.game_state.prize);
-        ctx.accounts.king.add_lamports(ctx.accounts.game_state.prize);
+        ctx.accounts.prize_pool.sub_lamports(ctx.accounts.game_state.prize)?;
+        ctx.accounts.king.add_lamports(ctx.accounts.game_state.prize)?;
+        check_prize_pool_invariant(&ctx.accounts.prize_pool, new_prize)?;
+
+        let game_key = ctx.accounts.game_state.key();
+        emit!(RefundPaid {
+            game: game_key,
+            recipient: ctx.accounts.king.key(),
+            amount: ctx.accounts.game_state.prize,
+        });
+
+        // Charges the house fee on top of the bid. When the bidder came through a referral
+        // link, part of the fee is credited to the referrer instead of the house.
+        let (mut house_cut, referral_cut) = ctx.accounts.game_state.split_fee(new_prize);
+        if let Some(referrer_balance) = ctx.accounts.referrer_balance.as_mut() {
+            require_keys_neq!(
+                referrer_balance.referrer,
+                ctx.accounts.payer.key(),
+                ErrorCode::SelfReferral
+            );
+            pay_lamports(
+                &ctx.accounts.system_program,
+                &ctx.accounts.payer,
+                referrer_balance.to_account_info(),
+                referral_cut,
+            )?;
+            referrer_balance.credit(referral_cut)?;
+
+            emit!(ReferralCredited {
+                game: game_key,
+                referrer: referrer_balance.referrer,
+                bidder: ctx.accounts.payer.key(),
+                amount: referral_cut,
+            });
+        } else {
+            house_cut += referral_cut;
+        }
+        pay_lamports(
+            &ctx.accounts.system_program,
+            &ctx.accounts.payer,
+            ctx.accounts.admin.to_account_info(),
+            house_cut,
+        )?;
+
+        // Ends the reign of the previous king. When the king outbids themselves, which the
+        // rules allow if forbid_king_rebid is off, king_stats and payer_stats are the same
+        // account. Anchor writes accounts back in the order they are declared, so the update
+        // goes through payer_stats, which is written last.
+        let now = clock.unix_timestamp;
+        let reign = now - ctx.accounts.game_state.crowned_at;
+        if ctx.accounts.payer.key() == ctx.accounts.king.key() {
+            ctx.accounts.payer_stats.record_reign(reign)?;
+        } else {
+            ctx.accounts.king_stats.record_reign(reign)?;
+        }
+
+        let payer_key = ctx.accounts.payer.key();
+        ctx.accounts.bid_history.push(payer_key, new_prize, now);
+        ctx.accounts
+            .payer_stats
+            .record_bid(game_key, payer_key, new_prize, clock.slot)?;
+        ctx.accounts.game_state.crowned_at = now;
+
+        emit!(NewKing {
+            game: game_key,
+            previous_king: ctx.accounts.king.key(),
+            new_king: payer_key,
+            old_prize: ctx.accounts.game_state.prize,
+            new_prize,
+            timestamp: now,
+        });
 
         // Sets the new bidder as the current king
         ctx.accounts.game_state.king = ctx.accounts.payer.key();
@@ -81,6 +209,479 @@ pub mod king_of_the_hill {
 
         Ok(())
     }
+
+    pub fn initialize_token_game(
+        ctx: Context<InitializeTokenGame>,
+        initial_prize: u64,
+    ) -> Result<()> {
+        require!(initial_prize > 0, ErrorCode::NeedInitialPrize);
+
+        // Moves the initial prize from the initial king's token account into the pool.
+        // transfer_checked also receives the mint and its decimals, so the token program
+        // can verify that the amount is expressed in the unit the caller expects. It is
+        // the only transfer instruction supported by every Token-2022 mint.
+        let received = deposit_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.initial_king_token_account,
+            &mut ctx.accounts.prize_pool,
+            &ctx.accounts.initial_king,
+            initial_prize,
+        )?;
+
+        let clock = Clock::get()?;
+        let now = clock.unix_timestamp;
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.king = ctx.accounts.initial_king.key();
+        game_state.admin = ctx.accounts.initial_king.key();
+        game_state.creator = ctx.accounts.initial_king.key();
+        game_state.bump = ctx.bumps.game_state;
+        game_state.prize_pool_bump = ctx.bumps.prize_pool;
+        // Records what actually reached the pool, which may be less than initial_prize
+        // if the mint charges a transfer fee.
+        game_state.prize = received;
+        game_state.mint = ctx.accounts.mint.key();
+        game_state.crowned_at = now;
+        game_state.rules = GameRules::default();
+        let game_counter = &mut ctx.accounts.game_counter;
+        game_state.generation = game_counter.games_created;
+        game_counter.games_created = game_counter
+            .games_created
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        let game_key = game_state.key();
+        ctx.accounts.bid_history.game = game_key;
+        ctx.accounts.bid_history.push(game_state.king, received, now);
+        ctx.accounts.initial_king_stats.record_bid(
+            game_key,
+            game_state.king,
+            received,
+            clock.slot,
+        )?;
+
+        emit!(GameCreated {
+            game: game_key,
+            mint: game_state.mint,
+            king: game_state.king,
+            prize: received,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn become_king_token(ctx: Context<BecomeKingToken>, new_prize: u64) -> Result<()> {
+        ctx.accounts.game_state.require_active()?;
+        let clock = Clock::get()?;
+        ctx.accounts.game_state.check_bid_rules(
+            ctx.accounts.payer.key(),
+            &ctx.accounts.payer_stats,
+            clock.slot,
+        )?;
+
+        let received = deposit_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.payer_token_account,
+            &mut ctx.accounts.prize_pool,
+            &ctx.accounts.payer,
+            new_prize,
+        )?;
+
+        // Checked against what actually reached the pool rather than new_prize: with a
+        // Token-2022 transfer fee the pool receives less than the bid, and the new king's
+        // stake must still beat the old one. The deposit is reverted along with the error.
+        require!(
+            received > ctx.accounts.game_state.prize,
+            ErrorCode::BidTooLow
+        );
+
+        // Refunds the previous king's stake from the pool
+        withdraw_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.prize_pool,
+            &ctx.accounts.king_token_account,
+            ctx.accounts.game_state.prize_pool_bump,
+            ctx.accounts.game_state.prize,
+        )?;
+
+        let game_key = ctx.accounts.game_state.key();
+        emit!(RefundPaid {
+            game: game_key,
+            recipient: ctx.accounts.game_state.king,
+            amount: ctx.accounts.game_state.prize,
+        });
+
+        let now = clock.unix_timestamp;
+        let reign = now - ctx.accounts.game_state.crowned_at;
+        if ctx.accounts.payer.key() == ctx.accounts.game_state.king {
+            ctx.accounts.payer_stats.record_reign(reign)?;
+        } else {
+            ctx.accounts.king_stats.record_reign(reign)?;
+        }
+
+        let payer_key = ctx.accounts.payer.key();
+        ctx.accounts.bid_history.push(payer_key, received, now);
+        ctx.accounts
+            .payer_stats
+            .record_bid(game_key, payer_key, received, clock.slot)?;
+
+        emit!(NewKing {
+            game: game_key,
+            previous_king: ctx.accounts.game_state.king,
+            new_king: payer_key,
+            old_prize: ctx.accounts.game_state.prize,
+            new_prize: received,
+            timestamp: now,
+        });
+
+        ctx.accounts.game_state.king = payer_key;
+        ctx.accounts.game_state.prize = received;
+        ctx.accounts.game_state.crowned_at = now;
+
+        Ok(())
+    }
+
+    // Sets the house fee charged on top of every bid in the lamport game, and the share of
+    // that fee credited to referrers. Both are in basis points (1/100th of a percent).
+    pub fn set_fees(
+        ctx: Context<SetFees>,
+        house_fee_bps: u16,
+        referral_share_bps: u16,
+    ) -> Result<()> {
+        require!(
+            house_fee_bps <= MAX_BPS && referral_share_bps <= MAX_BPS,
+            ErrorCode::InvalidFee
+        );
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.house_fee_bps = house_fee_bps;
+        game_state.referral_share_bps = referral_share_bps;
+
+        emit!(FeesUpdated {
+            game: game_state.key(),
+            house_fee_bps,
+            referral_share_bps,
+        });
+
+        Ok(())
+    }
+
+    // Creates the account that accumulates a referrer's commissions. A referral link only
+    // has to carry the referrer's wallet, bidders pass the balance PDA derived from it.
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        let referrer_balance = &mut ctx.accounts.referrer_balance;
+        referrer_balance.referrer = ctx.accounts.referrer.key();
+        referrer_balance.bump = ctx.bumps.referrer_balance;
+        Ok(())
+    }
+
+    // Withdraws all unclaimed commissions. The rent-exempt minimum stays in the account,
+    // so the referrer can keep earning.
+    pub fn claim_referral(ctx: Context<ClaimReferral>) -> Result<()> {
+        let amount = ctx.accounts.referrer_balance.unclaimed;
+        require!(amount > 0, ErrorCode::NothingToClaim);
+
+        ctx.accounts.referrer_balance.unclaimed = 0;
+        ctx.accounts.referrer_balance.sub_lamports(amount)?;
+        ctx.accounts.referrer.add_lamports(amount)?;
+
+        emit!(ReferralClaimed {
+            referrer: ctx.accounts.referrer.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    // Replaces the rules that bidders must follow. Takes effect from the next bid.
+    pub fn set_rules(ctx: Context<AdminOnly>, rules: GameRules) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        require!(
+            game_state.status != GameStatus::Finished,
+            ErrorCode::GameFinished
+        );
+        game_state.rules = rules;
+
+        emit!(RulesUpdated {
+            game: game_state.key(),
+            rules,
+        });
+
+        Ok(())
+    }
+
+    // Stops the game from accepting bids. The time spent paused doesn't count towards the
+    // reign of the king in their player stats.
+    pub fn pause(ctx: Context<AdminOnly>) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.require_active()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        game_state.status = GameStatus::Paused;
+        game_state.paused_at = now;
+
+        emit!(GamePaused {
+            game: game_state.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn unpause(ctx: Context<AdminOnly>) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        require!(
+            game_state.status == GameStatus::Paused,
+            ErrorCode::GameNotPaused
+        );
+
+        // Shifts the start of the reign forward by the length of the pause
+        let now = Clock::get()?.unix_timestamp;
+        game_state.crowned_at += now - game_state.paused_at;
+        game_state.status = GameStatus::Active;
+
+        emit!(GameUnpaused {
+            game: game_state.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Returns the current king's stake and finishes the game. Meant to get funds out of a
+    // game that has a bug, so it works whether the game is active or paused. The game
+    // accounts stay open with their rent, the creator gets it back with close_game, which is
+    // the required second step and only accepts a finished game.
+    pub fn emergency_refund(ctx: Context<EmergencyRefund>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let refund = ctx.accounts.game_state.prize;
+        let reign = end_game(&mut ctx.accounts.game_state, now)?;
+        ctx.accounts.king_stats.record_reign(reign)?;
+
+        ctx.accounts.prize_pool.sub_lamports(refund)?;
+        ctx.accounts.king.add_lamports(refund)?;
+        check_prize_pool_invariant(&ctx.accounts.prize_pool, 0)?;
+
+        emit!(RefundPaid {
+            game: ctx.accounts.game_state.key(),
+            recipient: ctx.accounts.king.key(),
+            amount: refund,
+        });
+
+        Ok(())
+    }
+
+    // Same as emergency_refund, followed by close_token_game
+    pub fn emergency_refund_token(ctx: Context<EmergencyRefundToken>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let refund = ctx.accounts.game_state.prize;
+        let reign = end_game(&mut ctx.accounts.game_state, now)?;
+        ctx.accounts.king_stats.record_reign(reign)?;
+
+        withdraw_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.prize_pool,
+            &ctx.accounts.king_token_account,
+            ctx.accounts.game_state.prize_pool_bump,
+            refund,
+        )?;
+
+        emit!(RefundPaid {
+            game: ctx.accounts.game_state.key(),
+            recipient: ctx.accounts.game_state.king,
+            amount: refund,
+        });
+
+        Ok(())
+    }
+
+    // Closes a finished game and returns the rent of its accounts to the creator. The
+    // lamport game lives at a fixed address, so closing it is also what allows a new
+    // lamport game to be initialized. Player stats are not closed, since there is one per
+    // player and the instruction can't know them all. They are seeded with the generation
+    // of the game, so the next game at this address uses new stats accounts instead.
+    pub fn close_game(ctx: Context<CloseGame>) -> Result<()> {
+        // game_state and bid_history are closed by the close constraints in CloseGame. The
+        // prize pool is an UncheckedAccount, so it has to be closed by hand. After a payout
+        // it only holds its rent-exempt minimum, plus any lamports sent to it directly,
+        // and all of it goes back to the creator.
+        let prize_pool = ctx.accounts.prize_pool.to_account_info();
+        let remaining = prize_pool.lamports();
+        prize_pool.sub_lamports(remaining)?;
+        ctx.accounts.creator.add_lamports(remaining)?;
+
+        // Hands the account back to the System Program with no data, which is how the
+        // runtime represents an account that doesn't exist.
+        prize_pool.assign(&System::id());
+        prize_pool.resize(0)?;
+
+        emit!(GameClosed {
+            game: ctx.accounts.game_state.key(),
+            rent_returned: remaining,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_token_game(ctx: Context<CloseTokenGame>) -> Result<()> {
+        // A token account can only be closed once it is empty. Tokens sent to the pool
+        // directly don't belong to any bid, so they are swept to the creator first.
+        let leftover = ctx.accounts.prize_pool.amount;
+        if leftover > 0 {
+            withdraw_tokens(
+                &ctx.accounts.token_program,
+                &ctx.accounts.mint,
+                &ctx.accounts.prize_pool,
+                &ctx.accounts.creator_token_account,
+                ctx.accounts.game_state.prize_pool_bump,
+                leftover,
+            )?;
+        }
+
+        let rent_returned = ctx.accounts.prize_pool.to_account_info().lamports();
+        let mint_key = ctx.accounts.mint.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"prize_pool",
+            mint_key.as_ref(),
+            &[ctx.accounts.game_state.prize_pool_bump],
+        ]];
+
+        // The pool is owned by the token program, so only the token program can close it
+        token_interface::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.prize_pool.to_account_info(),
+                destination: ctx.accounts.creator.to_account_info(),
+                authority: ctx.accounts.prize_pool.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        emit!(GameClosed {
+            game: ctx.accounts.game_state.key(),
+            rent_returned,
+        });
+
+        Ok(())
+    }
+}
+
+// Marks the game as finished. Returns the length of the final reign in seconds.
+fn end_game(game_state: &mut GameState, now: i64) -> Result<i64> {
+    require!(
+        game_state.status != GameStatus::Finished,
+        ErrorCode::GameFinished
+    );
+    let reign = game_state.reign_length(now);
+
+    game_state.status = GameStatus::Finished;
+    // The pool no longer holds the prize
+    game_state.prize = 0;
+    Ok(reign)
+}
+
+// Transfers lamports from a signer with a CPI to the System Program. Accounts owned by the
+// System Program can only be debited by it, even when the owner of the account signed.
+fn pay_lamports<'info>(
+    system_program: &Program<'info, System>,
+    from: &Signer<'info>,
+    to: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: from.to_account_info(),
+                to,
+            },
+        ),
+        amount,
+    )
+}
+
+// The lamport prize pool must always hold the recorded prize on top of its rent-exempt
+// minimum. If it didn't, a refund could leave the pool below the rent-exempt minimum or
+// pay out lamports that belong to no one's bid.
+fn check_prize_pool_invariant(prize_pool: &AccountInfo, prize: u64) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(prize_pool.data_len());
+    let required = prize
+        .checked_add(rent)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    require!(
+        prize_pool.lamports() >= required,
+        ErrorCode::PrizePoolUnderfunded
+    );
+    Ok(())
+}
+
+// Transfers `amount` tokens from a bidder into the prize pool and returns how many tokens
+// the pool actually received. Token-2022 mints with the transfer fee extension withhold part
+// of every transfer, so the recorded prize must come from the pool balance, not the bid.
+fn deposit_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    prize_pool: &mut InterfaceAccount<'info, TokenAccount>,
+    authority: &Signer<'info>,
+    amount: u64,
+) -> Result<u64> {
+    let balance_before = prize_pool.amount;
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: prize_pool.to_account_info(),
+                authority: authority.to_account_info(),
+            },
+        ),
+        amount,
+        mint.decimals,
+    )?;
+
+    // Anchor deserializes accounts once, before the instruction runs. reload() reads the
+    // account data again so the balance reflects the CPI above.
+    prize_pool.reload()?;
+    Ok(prize_pool.amount - balance_before)
+}
+
+// Transfers `amount` tokens out of the prize pool of the game played with `mint`
+fn withdraw_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    prize_pool: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    prize_pool_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    // The prize pool token account is its own authority, so only this program can
+    // move tokens out of it by signing the CPI with the seeds of the pool PDA.
+    let mint_key = mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[b"prize_pool", mint_key.as_ref(), &[prize_pool_bump]]];
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: prize_pool.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: prize_pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        mint.decimals,
+    )
 }
 
 
@@ -112,6 +713,38 @@ pub struct Initialize<'info> {
     // from AccountInfo. Unchecked accounts are efficient in this case where we only need to store
     // SOL and check the balance.
     pub prize_pool: UncheckedAccount<'info>,
+    #[account(init,
+        payer = initial_king,
+        space = DISCRIMINATOR + BidHistory::INIT_SPACE,
+        // Seeding with the game state address gives every game its own history
+        seeds = [b"bid_history", game_state.key().as_ref()],
+        bump,
+    )]
+    // Box moves the account to the heap. BidHistory is large enough that keeping it on the
+    // stack, together with the other accounts, could exceed the 4KB stack frame limit.
+    pub bid_history: Box<Account<'info, BidHistory>>,
+    // Counts the games created at this game state address. It is never closed, so it
+    // survives close_game and gives the next game at the same address a new generation.
+    #[account(init_if_needed,
+        payer = initial_king,
+        space = DISCRIMINATOR + GameCounter::INIT_SPACE,
+        seeds = [b"game_counter", game_state.key().as_ref()],
+        bump,
+    )]
+    pub game_counter: Account<'info, GameCounter>,
+    // Seeded with the generation the game is about to get, see initialize
+    #[account(init,
+        payer = initial_king,
+        space = DISCRIMINATOR + PlayerStats::INIT_SPACE,
+        seeds = [
+            b"player_stats",
+            game_state.key().as_ref(),
+            game_counter.games_created.to_le_bytes().as_ref(),
+            initial_king.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub initial_king_stats: Account<'info, PlayerStats>,
     // Reference to Solana's built-in System Program, which is required whenever creating new
     // accounts or transferring SOL.
     pub system_program: Program<'info, System>,
@@ -120,26 +753,377 @@ pub struct Initialize<'info> {
 #[derive(Accounts)]
 pub struct BecomeKing<'info> {
     #[account(mut, // The account is mutable because the current king needs to be updated
+        // Only the lamport game lives at this address. Without the seeds constraint, the
+        // state of a token game could be passed here and its prize refunded in lamports.
+        seeds = [b"game_state"],
+        // Verifies the address with the bump stored at initialize
+        bump = game_state.bump,
         // Constraint to check if the "king" field in GameState matches the key of the "king" 
         // field in BecomeKing
         has_one = king, 
+        // The house fee goes to the admin of the game
+        has_one = admin,
     )]
     pub game_state: Account<'info, GameState>,
     #[account(mut)]
     /// CHECK: This is okay - it's only receiving SOL and we don't need any other access
     pub king: UncheckedAccount<'info>,
     #[account(mut)]
+    /// CHECK: This is okay - it's only receiving SOL and we don't need any other access
+    pub admin: UncheckedAccount<'info>,
+    #[account(mut)]
     pub payer: Signer<'info>,
-    #[account(mut, seeds = [b"prize_pool"], bump)]
-    // seeds = [b"prize_pool"] is a constraint requiring that the account passed to this field
-    // is always equal to the PDA derived with the seeds [b"prize_pool"] and this program's ID. 
-    // If a different account is passed, the transaction will fail. This makes sure there is 
-    // only one valid prize pool address and only this program can own it.
+    #[account(mut,
+        constraint = prize_pool.key() == game_state.prize_pool_address()?
+            @ ErrorCode::InvalidPrizePoolAccount,
+    )]
+    // The constraint requires that the account passed to this field is always equal to the
+    // PDA derived with the seeds [b"prize_pool"], the bump stored in the game state and this
+    // program's ID. If a different account is passed, the transaction fails with
+    // InvalidPrizePoolAccount. This makes sure there is only one valid prize pool address
+    // and only this program can own it.
     /// CHECK: This is okay - it's only receiving SOL and we don't need any other access
     pub prize_pool: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"bid_history", game_state.key().as_ref()], bump)]
+    pub bid_history: Box<Account<'info, BidHistory>>,
+    // Stats of the king being dethroned. It must be declared before payer_stats, see
+    // become_king for why the order matters.
+    #[account(mut,
+        seeds = [
+            b"player_stats",
+            game_state.key().as_ref(),
+            game_state.generation.to_le_bytes().as_ref(),
+            king.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub king_stats: Account<'info, PlayerStats>,
+    // init_if_needed creates the account on a player's first bid and loads the existing
+    // one on every later bid.
+    #[account(init_if_needed,
+        payer = payer,
+        space = DISCRIMINATOR + PlayerStats::INIT_SPACE,
+        seeds = [
+            b"player_stats",
+            game_state.key().as_ref(),
+            game_state.generation.to_le_bytes().as_ref(),
+            payer.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub payer_stats: Account<'info, PlayerStats>,
+    // Option marks the account as optional. Clients pass the balance of the referrer when
+    // the bidder came through a referral link, or null otherwise.
+    #[account(mut,
+        seeds = [b"referrer", referrer_balance.referrer.as_ref()],
+        bump = referrer_balance.bump,
+    )]
+    pub referrer_balance: Option<Account<'info, ReferrerBalance>>,
     pub system_program: Program<'info, System>,
 }
 
+// Token games keep their state and prize pool at PDAs seeded with the mint, so each SPL or
+// Token-2022 mint can host its own game next to the lamport game.
+#[derive(Accounts)]
+pub struct InitializeTokenGame<'info> {
+    #[account(init,
+        payer = initial_king,
+        space = DISCRIMINATOR + GameState::INIT_SPACE,
+        seeds = [b"game_state", mint.key().as_ref()],
+        bump,
+    )]
+    pub game_state: Account<'info, GameState>,
+    #[account(mut)]
+    pub initial_king: Signer<'info>,
+    // InterfaceAccount deserializes accounts owned by either the Token or the Token-2022
+    // program, so the same instruction works with mints from both.
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut,
+        token::mint = mint,
+        token::authority = initial_king,
+        token::token_program = token_program,
+    )]
+    pub initial_king_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(init,
+        payer = initial_king,
+        seeds = [b"prize_pool", mint.key().as_ref()],
+        bump,
+        // The pool is a token account whose authority is the pool PDA itself
+        token::mint = mint,
+        token::authority = prize_pool,
+        token::token_program = token_program,
+    )]
+    pub prize_pool: InterfaceAccount<'info, TokenAccount>,
+    #[account(init,
+        payer = initial_king,
+        space = DISCRIMINATOR + BidHistory::INIT_SPACE,
+        seeds = [b"bid_history", game_state.key().as_ref()],
+        bump,
+    )]
+    pub bid_history: Box<Account<'info, BidHistory>>,
+    // Counts the games created at this game state address. It is never closed, so it
+    // survives close_game and gives the next game at the same address a new generation.
+    #[account(init_if_needed,
+        payer = initial_king,
+        space = DISCRIMINATOR + GameCounter::INIT_SPACE,
+        seeds = [b"game_counter", game_state.key().as_ref()],
+        bump,
+    )]
+    pub game_counter: Account<'info, GameCounter>,
+    // Seeded with the generation the game is about to get, see initialize
+    #[account(init,
+        payer = initial_king,
+        space = DISCRIMINATOR + PlayerStats::INIT_SPACE,
+        seeds = [
+            b"player_stats",
+            game_state.key().as_ref(),
+            game_counter.games_created.to_le_bytes().as_ref(),
+            initial_king.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub initial_king_stats: Account<'info, PlayerStats>,
+    // Either the Token or the Token-2022 program, whichever owns the mint
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BecomeKingToken<'info> {
+    #[account(mut,
+        seeds = [b"game_state", mint.key().as_ref()],
+        bump = game_state.bump,
+        has_one = mint,
+    )]
+    pub game_state: Account<'info, GameState>,
+    // Mutable because it pays for payer_stats on the first bid
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut,
+        token::mint = mint,
+        token::authority = payer,
+        token::token_program = token_program,
+    )]
+    pub payer_token_account: InterfaceAccount<'info, TokenAccount>,
+    // Receives the refund, so it must belong to the king recorded in the game state
+    #[account(mut,
+        token::mint = mint,
+        token::authority = game_state.king,
+        token::token_program = token_program,
+    )]
+    pub king_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut,
+        constraint = prize_pool.key() == game_state.prize_pool_address()?
+            @ ErrorCode::InvalidPrizePoolAccount,
+    )]
+    pub prize_pool: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"bid_history", game_state.key().as_ref()], bump)]
+    pub bid_history: Box<Account<'info, BidHistory>>,
+    #[account(mut,
+        seeds = [
+            b"player_stats",
+            game_state.key().as_ref(),
+            game_state.generation.to_le_bytes().as_ref(),
+            game_state.king.as_ref(),
+        ],
+        bump,
+    )]
+    pub king_stats: Account<'info, PlayerStats>,
+    #[account(init_if_needed,
+        payer = payer,
+        space = DISCRIMINATOR + PlayerStats::INIT_SPACE,
+        seeds = [
+            b"player_stats",
+            game_state.key().as_ref(),
+            game_state.generation.to_le_bytes().as_ref(),
+            payer.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub payer_stats: Account<'info, PlayerStats>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+// Shared by the instructions that only the admin of a game can call and that don't move funds
+#[derive(Accounts)]
+pub struct AdminOnly<'info> {
+    // Works for both lamport and token games, so no seeds are needed. has_one = admin checks
+    // that the signer is the admin recorded in this particular game.
+    #[account(mut, has_one = admin)]
+    pub game_state: Account<'info, GameState>,
+    pub admin: Signer<'info>,
+}
+
+// Fees are only charged in the lamport game
+#[derive(Accounts)]
+pub struct SetFees<'info> {
+    #[account(mut, seeds = [b"game_state"], bump = game_state.bump, has_one = admin)]
+    pub game_state: Account<'info, GameState>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(init,
+        payer = referrer,
+        space = DISCRIMINATOR + ReferrerBalance::INIT_SPACE,
+        seeds = [b"referrer", referrer.key().as_ref()],
+        bump,
+    )]
+    pub referrer_balance: Account<'info, ReferrerBalance>,
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferral<'info> {
+    #[account(mut,
+        seeds = [b"referrer", referrer.key().as_ref()],
+        bump = referrer_balance.bump,
+        has_one = referrer,
+    )]
+    pub referrer_balance: Account<'info, ReferrerBalance>,
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyRefund<'info> {
+    #[account(mut,
+        seeds = [b"game_state"],
+        bump = game_state.bump,
+        has_one = admin,
+        has_one = king,
+    )]
+    pub game_state: Account<'info, GameState>,
+    pub admin: Signer<'info>,
+    #[account(mut)]
+    /// CHECK: This is okay - it's only receiving SOL and is checked against game_state.king
+    pub king: UncheckedAccount<'info>,
+    #[account(mut,
+        constraint = prize_pool.key() == game_state.prize_pool_address()?
+            @ ErrorCode::InvalidPrizePoolAccount,
+    )]
+    /// CHECK: This is okay - it's a PDA that only holds SOL
+    pub prize_pool: UncheckedAccount<'info>,
+    #[account(mut,
+        seeds = [
+            b"player_stats",
+            game_state.key().as_ref(),
+            game_state.generation.to_le_bytes().as_ref(),
+            king.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub king_stats: Account<'info, PlayerStats>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyRefundToken<'info> {
+    #[account(mut,
+        seeds = [b"game_state", mint.key().as_ref()],
+        bump = game_state.bump,
+        has_one = admin,
+        has_one = mint,
+    )]
+    pub game_state: Account<'info, GameState>,
+    pub admin: Signer<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut,
+        token::mint = mint,
+        token::authority = game_state.king,
+        token::token_program = token_program,
+    )]
+    pub king_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut,
+        constraint = prize_pool.key() == game_state.prize_pool_address()?
+            @ ErrorCode::InvalidPrizePoolAccount,
+    )]
+    pub prize_pool: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut,
+        seeds = [
+            b"player_stats",
+            game_state.key().as_ref(),
+            game_state.generation.to_le_bytes().as_ref(),
+            game_state.king.as_ref(),
+        ],
+        bump,
+    )]
+    pub king_stats: Account<'info, PlayerStats>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGame<'info> {
+    #[account(mut,
+        seeds = [b"game_state"],
+        bump = game_state.bump,
+        has_one = creator,
+        // Only a game whose king has been refunded can be closed
+        constraint = game_state.status == GameStatus::Finished @ ErrorCode::GameNotFinished,
+        // Transfers all the lamports of the account to the creator once the instruction
+        // succeeds and marks the account as closed
+        close = creator,
+    )]
+    pub game_state: Account<'info, GameState>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(mut,
+        constraint = prize_pool.key() == game_state.prize_pool_address()?
+            @ ErrorCode::InvalidPrizePoolAccount,
+    )]
+    /// CHECK: This is okay - it's a PDA that only holds SOL
+    pub prize_pool: UncheckedAccount<'info>,
+    #[account(mut,
+        seeds = [b"bid_history", game_state.key().as_ref()],
+        bump,
+        close = creator,
+    )]
+    pub bid_history: Box<Account<'info, BidHistory>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTokenGame<'info> {
+    #[account(mut,
+        seeds = [b"game_state", mint.key().as_ref()],
+        bump = game_state.bump,
+        has_one = creator,
+        has_one = mint,
+        constraint = game_state.status == GameStatus::Finished @ ErrorCode::GameNotFinished,
+        close = creator,
+    )]
+    pub game_state: Account<'info, GameState>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    // Receives any tokens left in the pool
+    #[account(mut,
+        token::mint = mint,
+        token::authority = creator,
+        token::token_program = token_program,
+    )]
+    pub creator_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut,
+        constraint = prize_pool.key() == game_state.prize_pool_address()?
+            @ ErrorCode::InvalidPrizePoolAccount,
+    )]
+    pub prize_pool: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut,
+        seeds = [b"bid_history", game_state.key().as_ref()],
+        bump,
+        close = creator,
+    )]
+    pub bid_history: Box<Account<'info, BidHistory>>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
 // The #[account] attribute sets the discriminator for the new account and also
 // sets the owner of the account to the programId in the declare_id! macro
 //
@@ -150,9 +1134,312 @@ pub struct BecomeKing<'info> {
 #[account]
 #[derive(InitSpace)]
 pub struct GameState {
+    pub admin: Pubkey,
+    pub creator: Pubkey,
     pub king: Pubkey,
     pub prize: u64,
+    // Canonical bumps of the game state and prize pool PDAs
+    pub bump: u8,
     pub prize_pool_bump: u8,
+    // Mint of the prize token, or Pubkey::default() for a game played in lamports
+    pub mint: Pubkey,
+    // Unix timestamp at which the current king took the crown
+    pub crowned_at: i64,
+    pub status: GameStatus,
+    // Unix timestamp of the last pause, only meaningful while the game is paused
+    pub paused_at: i64,
+    pub rules: GameRules,
+    // House fee charged on top of each bid, in basis points. Only used by the lamport game.
+    pub house_fee_bps: u16,
+    // Share of the house fee credited to the referrer of a bid, in basis points
+    pub referral_share_bps: u16,
+    // Number of games created at this address before this one, part of the seeds of the
+    // player stats of the game
+    pub generation: u64,
+}
+
+impl GameState {
+    // Rejects bids that break the rules of the game. `bidder_stats` must belong to `bidder`.
+    pub fn check_bid_rules(
+        &self,
+        bidder: Pubkey,
+        bidder_stats: &PlayerStats,
+        slot: u64,
+    ) -> Result<()> {
+        if self.rules.forbid_king_rebid {
+            require_keys_neq!(bidder, self.king, ErrorCode::KingCannotBid);
+        }
+        // A player who has never bid has no last_bid_slot to wait for
+        if bidder_stats.times_crowned > 0 {
+            require!(
+                slot.saturating_sub(bidder_stats.last_bid_slot)
+                    >= self.rules.min_slots_between_bids,
+                ErrorCode::BidCooldownActive
+            );
+        }
+        Ok(())
+    }
+
+    // Computes the house fee charged on a bid and splits it into the part kept by the house
+    // and the part credited to a referrer. Returns (house_cut, referral_cut).
+    pub fn split_fee(&self, bid: u64) -> (u64, u64) {
+        // Both rates are at most MAX_BPS, so neither result can exceed the bid
+        let fee = (bid as u128 * self.house_fee_bps as u128 / MAX_BPS as u128) as u64;
+        let referral = (fee as u128 * self.referral_share_bps as u128 / MAX_BPS as u128) as u64;
+        (fee - referral, referral)
+    }
+
+    pub fn require_active(&self) -> Result<()> {
+        match self.status {
+            GameStatus::Active => Ok(()),
+            GameStatus::Paused => err!(ErrorCode::GamePaused),
+            GameStatus::Finished => err!(ErrorCode::GameFinished),
+        }
+    }
+
+    // Address of this game's prize pool. create_program_address with the stored bump costs
+    // far less compute than find_program_address, which tries bumps from 255 downwards
+    // until it finds one that is off the ed25519 curve.
+    pub fn prize_pool_address(&self) -> Result<Pubkey> {
+        let bump = [self.prize_pool_bump];
+        let seeds: &[&[u8]] = if self.mint == Pubkey::default() {
+            &[b"prize_pool", &bump]
+        } else {
+            &[b"prize_pool", self.mint.as_ref(), &bump]
+        };
+        Pubkey::create_program_address(seeds, &crate::ID)
+            .map_err(|_| error!(ErrorCode::InvalidPrizePoolAccount))
+    }
+
+    // Seconds the current king has held the crown, not counting an ongoing pause
+    pub fn reign_length(&self, now: i64) -> i64 {
+        let end = match self.status {
+            GameStatus::Paused => self.paused_at,
+            _ => now,
+        };
+        end - self.crowned_at
+    }
+}
+
+// Rules that every bid must follow, set by the admin of the game
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct GameRules {
+    // Rejects bids from the current king. Outbidding yourself only moves your own stake
+    // back and forth through the pool.
+    pub forbid_king_rebid: bool,
+    // Minimum number of slots between two bids from the same wallet. 0 disables the check.
+    pub min_slots_between_bids: u64,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            forbid_king_rebid: true,
+            // Prevents a wallet from bidding more than once in the same slot
+            min_slots_between_bids: 1,
+        }
+    }
+}
+
+// Enums stored in accounts are serialized as a single byte with the variant index
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum GameStatus {
+    // Accepting bids
+    Active,
+    // Stopped by the admin, bids are rejected until the game is unpaused
+    Paused,
+    // The king has been refunded and no more bids are accepted
+    Finished,
+}
+
+// A ring buffer with the most recent bids of a game. Once it is full, each new bid
+// overwrites the oldest one, so the account never needs to grow.
+#[account]
+#[derive(InitSpace)]
+pub struct BidHistory {
+    pub game: Pubkey,
+    // Number of bids ever recorded. The next bid is written at total_bids % BID_HISTORY_LEN.
+    pub total_bids: u64,
+    pub bids: [BidRecord; BID_HISTORY_LEN],
+}
+
+impl BidHistory {
+    pub fn push(&mut self, king: Pubkey, amount: u64, timestamp: i64) {
+        let index = (self.total_bids % BID_HISTORY_LEN as u64) as usize;
+        self.bids[index] = BidRecord {
+            king,
+            amount,
+            timestamp,
+        };
+        self.total_bids += 1;
+    }
+}
+
+// Types stored inside an account only need to be serializable, they don't get their own
+// discriminator like #[account] types do.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct BidRecord {
+    pub king: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+// Created next to the first game at a game state address and never closed
+#[account]
+#[derive(InitSpace)]
+pub struct GameCounter {
+    pub games_created: u64,
+}
+
+// Lifetime statistics of a player in a single game, used to build the leaderboard
+#[account]
+#[derive(InitSpace)]
+pub struct PlayerStats {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    // Sum of all winning bids placed by the player
+    pub total_bid: u64,
+    pub times_crowned: u32,
+    // Seconds spent as king in finished reigns. The ongoing reign of the current king is
+    // not included until they are dethroned, clients can add now - crowned_at.
+    pub total_reign_time: i64,
+    // Slot of the player's latest bid, used to enforce min_slots_between_bids
+    pub last_bid_slot: u64,
+}
+
+impl PlayerStats {
+    pub fn record_bid(
+        &mut self,
+        game: Pubkey,
+        player: Pubkey,
+        amount: u64,
+        slot: u64,
+    ) -> Result<()> {
+        // Accounts created by init_if_needed are zeroed, so this is the player's first bid
+        if self.player == Pubkey::default() {
+            self.game = game;
+            self.player = player;
+        }
+        self.total_bid = self
+            .total_bid
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.times_crowned = self
+            .times_crowned
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.last_bid_slot = slot;
+        Ok(())
+    }
+
+    pub fn record_reign(&mut self, seconds: i64) -> Result<()> {
+        self.total_reign_time = self
+            .total_reign_time
+            .checked_add(seconds)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+// Commissions owed to a referrer, held as lamports in the account itself
+#[account]
+#[derive(InitSpace)]
+pub struct ReferrerBalance {
+    pub referrer: Pubkey,
+    // Lamports credited and not yet claimed
+    pub unclaimed: u64,
+    // Lamports credited over the lifetime of the account
+    pub total_earned: u64,
+    pub bump: u8,
+}
+
+impl ReferrerBalance {
+    pub fn credit(&mut self, amount: u64) -> Result<()> {
+        self.unclaimed = self
+            .unclaimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.total_earned = self
+            .total_earned
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+// Events are not stored in any account. They are written to the transaction logs and can be
+// decoded by clients with the program IDL, e.g. with program.addEventListener in TypeScript.
+#[event]
+pub struct GameCreated {
+    pub game: Pubkey,
+    // Pubkey::default() for a game played in lamports
+    pub mint: Pubkey,
+    pub king: Pubkey,
+    pub prize: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NewKing {
+    pub game: Pubkey,
+    pub previous_king: Pubkey,
+    pub new_king: Pubkey,
+    pub old_prize: u64,
+    pub new_prize: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RefundPaid {
+    pub game: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct GamePaused {
+    pub game: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameUnpaused {
+    pub game: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RulesUpdated {
+    pub game: Pubkey,
+    pub rules: GameRules,
+}
+
+#[event]
+pub struct FeesUpdated {
+    pub game: Pubkey,
+    pub house_fee_bps: u16,
+    pub referral_share_bps: u16,
+}
+
+#[event]
+pub struct ReferralCredited {
+    pub game: Pubkey,
+    pub referrer: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ReferralClaimed {
+    pub referrer: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct GameClosed {
+    pub game: Pubkey,
+    // Lamports returned to the creator from the prize pool
+    pub rent_returned: u64,
 }
 
 #[error_code]
@@ -163,7 +1450,33 @@ pub enum ErrorCode {
     BidTooLow,
     #[msg("Invalid prize pool account")]
     InvalidPrizePoolAccount,
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    #[msg("The game has finished")]
+    GameFinished,
+    #[msg("The game is paused")]
+    GamePaused,
+    #[msg("The game is not paused")]
+    GameNotPaused,
+    #[msg("The prize pool doesn't hold enough lamports to cover the prize and rent")]
+    PrizePoolUnderfunded,
+    #[msg("The game has not finished")]
+    GameNotFinished,
+    #[msg("The current king cannot outbid themselves")]
+    KingCannotBid,
+    #[msg("Not enough slots have passed since this wallet's last bid")]
+    BidCooldownActive,
+    #[msg("Fees must be at most 10000 basis points")]
+    InvalidFee,
+    #[msg("A bidder cannot be their own referrer")]
+    SelfReferral,
+    #[msg("There is nothing to claim")]
+    NothingToClaim,
 }
 
 // The size of the account type discriminator
-const DISCRIMINATOR: usize = 8;
\ No newline at end of file
+const DISCRIMINATOR: usize = 8;
+// Number of bids kept in a game's BidHistory
+const BID_HISTORY_LEN: usize = 16;
+// Basis points in 100%, the unit of house_fee_bps and referral_share_bps
+const MAX_BPS: u16 = 10_000;
\ No newline at end of file
@@ -0,0 +1,1059 @@
+// Integration tests that run the compiled program in LiteSVM, an in-process Solana runtime.
+// They need target/deploy/king_of_the_hill.so, so they are ignored by plain `cargo test`.
+// Run them with `yarn test:rust:king-of-the-hill`, which builds the program first.
+use anchor_lang::{
+    solana_program::{program_option::COption, program_pack::Pack},
+    AccountSerialize, InstructionData, Space, ToAccountMetas,
+};
+use anchor_spl::{
+    token::spl_token::{self, state::AccountState},
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::{TransferFee, TransferFeeAmount, TransferFeeConfig},
+            BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+        },
+    },
+};
+use clients::king_of_the_hill::{
+    become_king, become_king_accounts, become_king_token, bid_history_address, claim_referral,
+    close_game, emergency_refund, emergency_refund_token, game_counter_address, game_state_address,
+    initialize, initialize_token_game, player_stats_address, prize_pool_address,
+    referrer_balance_address, register_referrer, set_fees, set_rules, token_game_state_address,
+    token_prize_pool_address,
+};
+use king_of_the_hill::{
+    accounts, instruction, BidHistory, ErrorCode, GameCounter, GameRules, GameState, GameStatus,
+    PlayerStats, ReferrerBalance,
+};
+use litesvm::LiteSVM;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use test_utils::{assert_error, balance, fetch, funded_wallet, is_closed, send, SIGNATURE_FEE};
+
+// Space of the prize pool PDA, see Initialize
+const PRIZE_POOL_SPACE: usize = 8 + 8;
+// Fees used by the referral tests: 5% of each bid, a fifth of which goes to the referrer
+const HOUSE_FEE_BPS: u16 = 500;
+const REFERRAL_SHARE_BPS: u16 = 2_000;
+
+struct Game {
+    svm: LiteSVM,
+    initial_king: Keypair,
+}
+
+// Loads the program and creates the lamport game with a 1 SOL prize
+fn setup() -> Game {
+    let mut svm = LiteSVM::new();
+    svm.add_program(
+        king_of_the_hill::ID,
+        &test_utils::program_binary("king_of_the_hill"),
+    )
+    .expect("the program should load");
+
+    let initial_king = funded_wallet(&mut svm);
+    send(
+        &mut svm,
+        initialize(&initial_king.pubkey(), LAMPORTS_PER_SOL, 0),
+        &initial_king,
+    )
+    .expect("initialize should succeed");
+
+    Game { svm, initial_king }
+}
+
+// Builds become_king from accounts that a test has tampered with, see become_king_accounts
+fn become_king_with(accounts: accounts::BecomeKing, new_prize: u64) -> Instruction {
+    Instruction {
+        program_id: king_of_the_hill::ID,
+        accounts: accounts.to_account_metas(None),
+        data: instruction::BecomeKing { new_prize }.data(),
+    }
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn initialize_creates_the_game() {
+    let Game { svm, initial_king } = setup();
+
+    let game: GameState = fetch(&svm, &game_state_address());
+    assert_eq!(game.king, initial_king.pubkey());
+    assert_eq!(game.admin, initial_king.pubkey());
+    assert_eq!(game.prize, LAMPORTS_PER_SOL);
+
+    // The pool holds the prize on top of its own rent-exempt minimum
+    let rent = svm.minimum_balance_for_rent_exemption(PRIZE_POOL_SPACE);
+    assert_eq!(
+        balance(&svm, &prize_pool_address()),
+        rent + LAMPORTS_PER_SOL
+    );
+
+    let stats: PlayerStats = fetch(
+        &svm,
+        &player_stats_address(&game_state_address(), 0, &initial_king.pubkey()),
+    );
+    assert_eq!(stats.total_bid, LAMPORTS_PER_SOL);
+    assert_eq!(stats.times_crowned, 1);
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn emergency_refund_then_close_returns_every_lamport() {
+    let Game {
+        mut svm,
+        initial_king,
+    } = setup();
+    let challenger = funded_wallet(&mut svm);
+    let game: GameState = fetch(&svm, &game_state_address());
+    send(
+        &mut svm,
+        become_king(&game, &challenger.pubkey(), 2 * LAMPORTS_PER_SOL, None),
+        &challenger,
+    )
+    .unwrap();
+
+    // The refund pays the king back and finishes the game, without closing anything
+    let challenger_before = balance(&svm, &challenger.pubkey());
+    let admin_before = balance(&svm, &initial_king.pubkey());
+    let pool_rent = svm.minimum_balance_for_rent_exemption(PRIZE_POOL_SPACE);
+    let game: GameState = fetch(&svm, &game_state_address());
+    send(&mut svm, emergency_refund(&game), &initial_king).unwrap();
+
+    assert_eq!(
+        balance(&svm, &challenger.pubkey()),
+        challenger_before + 2 * LAMPORTS_PER_SOL
+    );
+    assert_eq!(
+        balance(&svm, &initial_king.pubkey()),
+        admin_before - SIGNATURE_FEE
+    );
+    assert_eq!(balance(&svm, &prize_pool_address()), pool_rent);
+    let game: GameState = fetch(&svm, &game_state_address());
+    assert!(game.status == GameStatus::Finished);
+    assert_eq!(game.prize, 0);
+
+    // Closing the game is the second step, which returns the rent of the game accounts
+    let game_state_rent = balance(&svm, &game_state_address());
+    let bid_history_rent = balance(&svm, &bid_history_address(&game_state_address()));
+    let creator_before = balance(&svm, &initial_king.pubkey());
+    send(&mut svm, close_game(&game), &initial_king).unwrap();
+
+    assert_eq!(
+        balance(&svm, &initial_king.pubkey()),
+        creator_before + game_state_rent + bid_history_rent + pool_rent - SIGNATURE_FEE
+    );
+    assert!(is_closed(&svm, &game_state_address()));
+    assert!(is_closed(&svm, &prize_pool_address()));
+    assert!(is_closed(&svm, &bid_history_address(&game_state_address())));
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn active_game_cannot_be_closed() {
+    let Game {
+        mut svm,
+        initial_king,
+    } = setup();
+
+    let game: GameState = fetch(&svm, &game_state_address());
+    let result = send(&mut svm, close_game(&game), &initial_king);
+
+    assert_error(result, ErrorCode::GameNotFinished);
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn closed_game_can_be_initialized_again_with_fresh_stats() {
+    let Game {
+        mut svm,
+        initial_king,
+    } = setup();
+
+    let game: GameState = fetch(&svm, &game_state_address());
+    send(&mut svm, emergency_refund(&game), &initial_king).unwrap();
+    let game: GameState = fetch(&svm, &game_state_address());
+    assert!(game.status == GameStatus::Finished);
+    send(&mut svm, close_game(&game), &initial_king).unwrap();
+
+    // The stats of the closed game are left behind, and the same player starts the new game
+    let new_prize = 3 * LAMPORTS_PER_SOL;
+    send(
+        &mut svm,
+        initialize(&initial_king.pubkey(), new_prize, 1),
+        &initial_king,
+    )
+    .expect("a closed game should be replaced by a new one");
+
+    let game: GameState = fetch(&svm, &game_state_address());
+    assert_eq!(game.generation, 1);
+    assert_eq!(game.prize, new_prize);
+    let counter: GameCounter = fetch(&svm, &game_counter_address(&game_state_address()));
+    assert_eq!(counter.games_created, 2);
+
+    // The new game has its own stats, with nothing carried over from the first game
+    let stats: PlayerStats = fetch(
+        &svm,
+        &player_stats_address(&game_state_address(), 1, &initial_king.pubkey()),
+    );
+    assert_eq!(stats.total_bid, new_prize);
+    assert_eq!(stats.times_crowned, 1);
+    assert_eq!(stats.total_reign_time, 0);
+    let old_stats: PlayerStats = fetch(
+        &svm,
+        &player_stats_address(&game_state_address(), 0, &initial_king.pubkey()),
+    );
+    assert_eq!(old_stats.total_bid, LAMPORTS_PER_SOL);
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn higher_bid_makes_a_new_king_and_refunds_the_old_one() {
+    let Game {
+        mut svm,
+        initial_king,
+    } = setup();
+    let challenger = funded_wallet(&mut svm);
+    let new_prize = 2 * LAMPORTS_PER_SOL;
+
+    let king_before = balance(&svm, &initial_king.pubkey());
+    let challenger_before = balance(&svm, &challenger.pubkey());
+    let pool_before = balance(&svm, &prize_pool_address());
+
+    let game: GameState = fetch(&svm, &game_state_address());
+    send(
+        &mut svm,
+        become_king(&game, &challenger.pubkey(), new_prize, None),
+        &challenger,
+    )
+    .unwrap();
+
+    let game: GameState = fetch(&svm, &game_state_address());
+    assert_eq!(game.king, challenger.pubkey());
+    assert_eq!(game.prize, new_prize);
+
+    // The old king gets exactly their stake back
+    assert_eq!(
+        balance(&svm, &initial_king.pubkey()),
+        king_before + LAMPORTS_PER_SOL
+    );
+    // The pool swaps the old prize for the new one
+    assert_eq!(
+        balance(&svm, &prize_pool_address()),
+        pool_before - LAMPORTS_PER_SOL + new_prize
+    );
+    // The challenger pays the bid, the rent of their stats account and the transaction fee
+    let stats_rent = svm.minimum_balance_for_rent_exemption(8 + PlayerStats::INIT_SPACE);
+    assert_eq!(
+        balance(&svm, &challenger.pubkey()),
+        challenger_before - new_prize - stats_rent - SIGNATURE_FEE
+    );
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn bid_equal_to_the_prize_is_rejected() {
+    let Game { mut svm, .. } = setup();
+    let challenger = funded_wallet(&mut svm);
+
+    let game: GameState = fetch(&svm, &game_state_address());
+    let result = send(
+        &mut svm,
+        become_king(&game, &challenger.pubkey(), LAMPORTS_PER_SOL, None),
+        &challenger,
+    );
+
+    assert_error(result, ErrorCode::BidTooLow);
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn king_cannot_outbid_themselves() {
+    let Game {
+        mut svm,
+        initial_king,
+    } = setup();
+
+    let game: GameState = fetch(&svm, &game_state_address());
+    let result = send(
+        &mut svm,
+        become_king(&game, &initial_king.pubkey(), 2 * LAMPORTS_PER_SOL, None),
+        &initial_king,
+    );
+
+    assert_error(result, ErrorCode::KingCannotBid);
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn outbid_player_waits_for_the_cooldown_before_bidding_again() {
+    let Game {
+        mut svm,
+        initial_king,
+    } = setup();
+    let rules = GameRules {
+        forbid_king_rebid: true,
+        min_slots_between_bids: 10,
+    };
+    send(
+        &mut svm,
+        set_rules(&game_state_address(), &initial_king.pubkey(), rules),
+        &initial_king,
+    )
+    .unwrap();
+    let first = funded_wallet(&mut svm);
+    let second = funded_wallet(&mut svm);
+
+    // Neither player has bid yet, so there is no cooldown to wait for
+    let game: GameState = fetch(&svm, &game_state_address());
+    send(
+        &mut svm,
+        become_king(&game, &first.pubkey(), 2 * LAMPORTS_PER_SOL, None),
+        &first,
+    )
+    .unwrap();
+    let game: GameState = fetch(&svm, &game_state_address());
+    send(
+        &mut svm,
+        become_king(&game, &second.pubkey(), 3 * LAMPORTS_PER_SOL, None),
+        &second,
+    )
+    .unwrap();
+
+    // The outbid player can't take the crown back before min_slots_between_bids have passed
+    svm.warp_to_slot(9);
+    let game: GameState = fetch(&svm, &game_state_address());
+    let result = send(
+        &mut svm,
+        become_king(&game, &first.pubkey(), 4 * LAMPORTS_PER_SOL, None),
+        &first,
+    );
+    assert_error(result, ErrorCode::BidCooldownActive);
+
+    svm.warp_to_slot(10);
+    send(
+        &mut svm,
+        become_king(&game, &first.pubkey(), 4 * LAMPORTS_PER_SOL, None),
+        &first,
+    )
+    .unwrap();
+    let game: GameState = fetch(&svm, &game_state_address());
+    assert_eq!(game.king, first.pubkey());
+    assert_eq!(game.rules.min_slots_between_bids, 10);
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn bid_history_overwrites_the_oldest_bid_once_full() {
+    let Game {
+        mut svm,
+        initial_king,
+    } = setup();
+    let players = [funded_wallet(&mut svm), funded_wallet(&mut svm)];
+    let history: BidHistory = fetch(&svm, &bid_history_address(&game_state_address()));
+    let capacity = history.bids.len();
+
+    // Every bid made in the game, starting with the initial stake. Two players take turns so
+    // nobody outbids themselves, one slot apart to respect the default cooldown.
+    let mut bids = vec![(initial_king.pubkey(), LAMPORTS_PER_SOL)];
+    for round in 1..=capacity + 3 {
+        svm.warp_to_slot(round as u64);
+        let player = &players[round % 2];
+        let new_prize = LAMPORTS_PER_SOL + round as u64;
+        let game: GameState = fetch(&svm, &game_state_address());
+        send(
+            &mut svm,
+            become_king(&game, &player.pubkey(), new_prize, None),
+            player,
+        )
+        .unwrap();
+        bids.push((player.pubkey(), new_prize));
+    }
+
+    let history: BidHistory = fetch(&svm, &bid_history_address(&game_state_address()));
+    let total_bids = bids.len();
+    assert_eq!(history.total_bids, total_bids as u64);
+    // The buffer holds the latest `capacity` bids, each at its bid number modulo capacity
+    for (number, (king, amount)) in bids.iter().enumerate().skip(total_bids - capacity) {
+        let record = &history.bids[number % capacity];
+        assert_eq!(record.king, *king);
+        assert_eq!(record.amount, *amount);
+    }
+    // The next bid goes where the oldest remaining one is, which already replaced the
+    // initial stake
+    let oldest = &history.bids[total_bids % capacity];
+    assert_eq!(oldest.amount, bids[total_bids - capacity].1);
+    assert!(history
+        .bids
+        .iter()
+        .all(|record| record.amount != LAMPORTS_PER_SOL));
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn house_fee_goes_to_the_admin_without_a_referrer() {
+    let Game {
+        mut svm,
+        initial_king,
+    } = setup();
+    send(
+        &mut svm,
+        set_fees(&initial_king.pubkey(), HOUSE_FEE_BPS, REFERRAL_SHARE_BPS),
+        &initial_king,
+    )
+    .unwrap();
+    let challenger = funded_wallet(&mut svm);
+    let new_prize = 2 * LAMPORTS_PER_SOL;
+    // 5% of the bid
+    let fee = new_prize / 20;
+
+    let admin_before = balance(&svm, &initial_king.pubkey());
+    let challenger_before = balance(&svm, &challenger.pubkey());
+    let game: GameState = fetch(&svm, &game_state_address());
+    send(
+        &mut svm,
+        become_king(&game, &challenger.pubkey(), new_prize, None),
+        &challenger,
+    )
+    .unwrap();
+
+    // The initial king is also the admin, so they get their stake back and the whole fee,
+    // including the share that would have gone to a referrer
+    assert_eq!(
+        balance(&svm, &initial_king.pubkey()),
+        admin_before + LAMPORTS_PER_SOL + fee
+    );
+    let stats_rent = svm.minimum_balance_for_rent_exemption(8 + PlayerStats::INIT_SPACE);
+    assert_eq!(
+        balance(&svm, &challenger.pubkey()),
+        challenger_before - new_prize - fee - stats_rent - SIGNATURE_FEE
+    );
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn referrer_is_credited_their_share_and_can_claim_it() {
+    let Game {
+        mut svm,
+        initial_king,
+    } = setup();
+    send(
+        &mut svm,
+        set_fees(&initial_king.pubkey(), HOUSE_FEE_BPS, REFERRAL_SHARE_BPS),
+        &initial_king,
+    )
+    .unwrap();
+    let referrer = funded_wallet(&mut svm);
+    send(&mut svm, register_referrer(&referrer.pubkey()), &referrer).unwrap();
+    let balance_address = referrer_balance_address(&referrer.pubkey());
+    let balance_rent = svm.minimum_balance_for_rent_exemption(8 + ReferrerBalance::INIT_SPACE);
+    assert_eq!(balance(&svm, &balance_address), balance_rent);
+
+    let challenger = funded_wallet(&mut svm);
+    let new_prize = 2 * LAMPORTS_PER_SOL;
+    let fee = new_prize / 20;
+    let referral = fee / 5;
+
+    let admin_before = balance(&svm, &initial_king.pubkey());
+    let challenger_before = balance(&svm, &challenger.pubkey());
+    let game: GameState = fetch(&svm, &game_state_address());
+    send(
+        &mut svm,
+        become_king(
+            &game,
+            &challenger.pubkey(),
+            new_prize,
+            Some(&referrer.pubkey()),
+        ),
+        &challenger,
+    )
+    .unwrap();
+
+    // The bidder pays the same fee with or without a referrer, only its split changes
+    assert_eq!(
+        balance(&svm, &initial_king.pubkey()),
+        admin_before + LAMPORTS_PER_SOL + fee - referral
+    );
+    let stats_rent = svm.minimum_balance_for_rent_exemption(8 + PlayerStats::INIT_SPACE);
+    assert_eq!(
+        balance(&svm, &challenger.pubkey()),
+        challenger_before - new_prize - fee - stats_rent - SIGNATURE_FEE
+    );
+    assert_eq!(balance(&svm, &balance_address), balance_rent + referral);
+    let credited: ReferrerBalance = fetch(&svm, &balance_address);
+    assert_eq!(credited.unclaimed, referral);
+    assert_eq!(credited.total_earned, referral);
+
+    let referrer_before = balance(&svm, &referrer.pubkey());
+    send(&mut svm, claim_referral(&referrer.pubkey()), &referrer).unwrap();
+
+    // The claim pays out the commission and leaves the rent in the account, which stays
+    // open for the next referral
+    assert_eq!(
+        balance(&svm, &referrer.pubkey()),
+        referrer_before + referral - SIGNATURE_FEE
+    );
+    assert_eq!(balance(&svm, &balance_address), balance_rent);
+    let claimed: ReferrerBalance = fetch(&svm, &balance_address);
+    assert_eq!(claimed.unclaimed, 0);
+    assert_eq!(claimed.total_earned, referral);
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn bidder_cannot_refer_themselves() {
+    let Game { mut svm, .. } = setup();
+    let challenger = funded_wallet(&mut svm);
+    send(
+        &mut svm,
+        register_referrer(&challenger.pubkey()),
+        &challenger,
+    )
+    .unwrap();
+
+    let game: GameState = fetch(&svm, &game_state_address());
+    let result = send(
+        &mut svm,
+        become_king(
+            &game,
+            &challenger.pubkey(),
+            2 * LAMPORTS_PER_SOL,
+            Some(&challenger.pubkey()),
+        ),
+        &challenger,
+    );
+
+    assert_error(result, ErrorCode::SelfReferral);
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn fee_above_100_percent_is_rejected() {
+    let Game {
+        mut svm,
+        initial_king,
+    } = setup();
+
+    let result = send(
+        &mut svm,
+        set_fees(&initial_king.pubkey(), 10_001, REFERRAL_SHARE_BPS),
+        &initial_king,
+    );
+    assert_error(result, ErrorCode::InvalidFee);
+
+    let result = send(
+        &mut svm,
+        set_fees(&initial_king.pubkey(), HOUSE_FEE_BPS, 10_001),
+        &initial_king,
+    );
+    assert_error(result, ErrorCode::InvalidFee);
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn claim_with_nothing_credited_is_rejected() {
+    let Game { mut svm, .. } = setup();
+    let referrer = funded_wallet(&mut svm);
+    send(&mut svm, register_referrer(&referrer.pubkey()), &referrer).unwrap();
+
+    let result = send(&mut svm, claim_referral(&referrer.pubkey()), &referrer);
+
+    assert_error(result, ErrorCode::NothingToClaim);
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn spoofed_prize_pool_is_rejected() {
+    let Game { mut svm, .. } = setup();
+    let attacker = funded_wallet(&mut svm);
+
+    // A PDA of this program derived from other seeds, so it passes an owner check
+    let (fake_pool, _) = Pubkey::find_program_address(&[b"fake_pool"], &king_of_the_hill::ID);
+    let game: GameState = fetch(&svm, &game_state_address());
+    let mut accounts = become_king_accounts(&game, &attacker.pubkey(), None);
+    accounts.prize_pool = fake_pool;
+
+    let result = send(
+        &mut svm,
+        become_king_with(accounts, 2 * LAMPORTS_PER_SOL),
+        &attacker,
+    );
+
+    assert_error(result, ErrorCode::InvalidPrizePoolAccount);
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn spoofed_king_is_rejected() {
+    let Game { mut svm, .. } = setup();
+    let attacker = funded_wallet(&mut svm);
+
+    // The attacker tries to receive the refund that belongs to the current king
+    let game: GameState = fetch(&svm, &game_state_address());
+    let mut accounts = become_king_accounts(&game, &attacker.pubkey(), None);
+    accounts.king = attacker.pubkey();
+
+    let result = send(
+        &mut svm,
+        become_king_with(accounts, 2 * LAMPORTS_PER_SOL),
+        &attacker,
+    );
+
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn spoofed_game_state_is_rejected() {
+    let Game { mut svm, .. } = setup();
+    let attacker = funded_wallet(&mut svm);
+
+    // Forges a game state owned by the program, at an address that isn't the game state
+    // PDA, claiming that the attacker is the king of a huge prize. If it were accepted,
+    // the refund would drain the real pool into the attacker's wallet.
+    let mut forged: GameState = fetch(&svm, &game_state_address());
+    forged.king = attacker.pubkey();
+    forged.prize = 9 * LAMPORTS_PER_SOL;
+    let mut data = Vec::new();
+    forged.try_serialize(&mut data).unwrap();
+
+    let forged_address = Pubkey::new_unique();
+    svm.set_account(
+        forged_address,
+        Account {
+            lamports: LAMPORTS_PER_SOL,
+            data,
+            owner: king_of_the_hill::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+
+    // The attacker outbids their own forged game from a second wallet. Every other
+    // account is the real one, so that only the game state is off.
+    let challenger = funded_wallet(&mut svm);
+    let real: GameState = fetch(&svm, &game_state_address());
+    let mut accounts = become_king_accounts(&real, &challenger.pubkey(), None);
+    accounts.game_state = forged_address;
+    accounts.king = attacker.pubkey();
+
+    let result = send(
+        &mut svm,
+        become_king_with(accounts, 9 * LAMPORTS_PER_SOL + 1),
+        &challenger,
+    );
+
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintSeeds);
+}
+
+// Token games are played the same way with mints of the Token and the Token-2022 program,
+// so every token test runs once with each.
+const TOKEN_PROGRAMS: [Pubkey; 2] = [spl_token::ID, spl_token_2022::ID];
+const DECIMALS: u8 = 6;
+// One whole token
+const TOKEN: u64 = 1_000_000;
+
+struct TokenGame {
+    svm: LiteSVM,
+    token_program: Pubkey,
+    mint: Pubkey,
+    initial_king: Keypair,
+    initial_king_tokens: Pubkey,
+}
+
+// Loads the program and creates a token game with a prize of one token, played with a new
+// mint of `token_program`
+fn setup_token_game(token_program: Pubkey) -> TokenGame {
+    let mut svm = LiteSVM::new();
+    svm.add_program(
+        king_of_the_hill::ID,
+        &test_utils::program_binary("king_of_the_hill"),
+    )
+    .expect("the program should load");
+
+    let mint = create_mint(&mut svm, &token_program);
+    let initial_king = funded_wallet(&mut svm);
+    let initial_king_tokens = create_token_account(
+        &mut svm,
+        &token_program,
+        &mint,
+        &initial_king.pubkey(),
+        10 * TOKEN,
+    );
+    send(
+        &mut svm,
+        initialize_token_game(
+            &token_program,
+            &mint,
+            &initial_king.pubkey(),
+            &initial_king_tokens,
+            TOKEN,
+            0,
+        ),
+        &initial_king,
+    )
+    .expect("initialize_token_game should succeed");
+
+    TokenGame {
+        svm,
+        token_program,
+        mint,
+        initial_king,
+        initial_king_tokens,
+    }
+}
+
+// Writes a mint without extensions directly into the runtime. The base layout of a mint is
+// the same in both token programs.
+fn create_mint(svm: &mut LiteSVM, token_program: &Pubkey) -> Pubkey {
+    let mint = spl_token::state::Mint {
+        mint_authority: COption::Some(Pubkey::new_unique()),
+        supply: 1_000 * TOKEN,
+        decimals: DECIMALS,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    mint.pack_into_slice(&mut data);
+    set_token_program_account(svm, token_program, data)
+}
+
+fn create_token_account(
+    svm: &mut LiteSVM,
+    token_program: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let account = spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: AccountState::Initialized,
+        ..Default::default()
+    };
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    account.pack_into_slice(&mut data);
+    set_token_program_account(svm, token_program, data)
+}
+
+fn set_token_program_account(svm: &mut LiteSVM, token_program: &Pubkey, data: Vec<u8>) -> Pubkey {
+    let address = Pubkey::new_unique();
+    svm.set_account(
+        address,
+        Account {
+            lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: *token_program,
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+    address
+}
+
+// Transfer fee of the mint written by create_transfer_fee_mint, 10% of every transfer
+const TRANSFER_FEE_BPS: u16 = 1_000;
+
+// Writes a Token-2022 mint with a transfer fee of TRANSFER_FEE_BPS and no maximum
+fn create_transfer_fee_mint(svm: &mut LiteSVM) -> Pubkey {
+    let len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::TransferFeeConfig,
+    ])
+    .unwrap();
+    let mut data = vec![0; len];
+    let mut state =
+        StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(&mut data)
+            .unwrap();
+    let fee = TransferFee {
+        epoch: 0.into(),
+        maximum_fee: u64::MAX.into(),
+        transfer_fee_basis_points: TRANSFER_FEE_BPS.into(),
+    };
+    let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+    config.older_transfer_fee = fee;
+    config.newer_transfer_fee = fee;
+    state.base = spl_token_2022::state::Mint {
+        mint_authority: COption::Some(Pubkey::new_unique()),
+        supply: 1_000 * TOKEN,
+        decimals: DECIMALS,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    set_token_program_account(svm, &spl_token_2022::ID, data)
+}
+
+// Token accounts of a mint with a transfer fee need room to accumulate the withheld fees
+fn create_transfer_fee_token_account(
+    svm: &mut LiteSVM,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&[
+        ExtensionType::TransferFeeAmount,
+    ])
+    .unwrap();
+    let mut data = vec![0; len];
+    let mut state =
+        StateWithExtensionsMut::<spl_token_2022::state::Account>::unpack_uninitialized(&mut data)
+            .unwrap();
+    state.init_extension::<TransferFeeAmount>(true).unwrap();
+    state.base = spl_token_2022::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: spl_token_2022::state::AccountState::Initialized,
+        ..Default::default()
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    set_token_program_account(svm, &spl_token_2022::ID, data)
+}
+
+fn token_balance(svm: &LiteSVM, token_account: &Pubkey) -> u64 {
+    let account = svm
+        .get_account(token_account)
+        .expect("token account should exist");
+    // Token-2022 accounts may have extensions after the base layout
+    spl_token::state::Account::unpack_from_slice(&account.data[..spl_token::state::Account::LEN])
+        .expect("token account should decode")
+        .amount
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn token_bid_moves_the_stake_into_the_pool_and_refunds_the_king() {
+    for token_program in TOKEN_PROGRAMS {
+        let TokenGame {
+            mut svm,
+            token_program,
+            mint,
+            initial_king_tokens,
+            ..
+        } = setup_token_game(token_program);
+        let pool = token_prize_pool_address(&mint);
+        assert_eq!(token_balance(&svm, &pool), TOKEN);
+        assert_eq!(token_balance(&svm, &initial_king_tokens), 9 * TOKEN);
+
+        let challenger = funded_wallet(&mut svm);
+        let challenger_tokens = create_token_account(
+            &mut svm,
+            &token_program,
+            &mint,
+            &challenger.pubkey(),
+            10 * TOKEN,
+        );
+        let game: GameState = fetch(&svm, &token_game_state_address(&mint));
+        send(
+            &mut svm,
+            become_king_token(
+                &game,
+                &token_program,
+                &challenger.pubkey(),
+                &challenger_tokens,
+                &initial_king_tokens,
+                3 * TOKEN,
+            ),
+            &challenger,
+        )
+        .unwrap();
+
+        // The pool swaps the old stake for the new one, and the old king is made whole
+        assert_eq!(token_balance(&svm, &pool), 3 * TOKEN);
+        assert_eq!(token_balance(&svm, &challenger_tokens), 7 * TOKEN);
+        assert_eq!(token_balance(&svm, &initial_king_tokens), 10 * TOKEN);
+        let game: GameState = fetch(&svm, &token_game_state_address(&mint));
+        assert_eq!(game.king, challenger.pubkey());
+        assert_eq!(game.prize, 3 * TOKEN);
+    }
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn token_emergency_refund_is_signed_by_the_pool() {
+    for token_program in TOKEN_PROGRAMS {
+        let TokenGame {
+            mut svm,
+            token_program,
+            mint,
+            initial_king,
+            initial_king_tokens,
+        } = setup_token_game(token_program);
+
+        // The pool is its own authority, so the refund only succeeds if the program signs
+        // the transfer with the seeds of the pool PDA
+        let game: GameState = fetch(&svm, &token_game_state_address(&mint));
+        send(
+            &mut svm,
+            emergency_refund_token(&game, &token_program, &initial_king_tokens),
+            &initial_king,
+        )
+        .unwrap();
+
+        assert_eq!(token_balance(&svm, &token_prize_pool_address(&mint)), 0);
+        assert_eq!(token_balance(&svm, &initial_king_tokens), 10 * TOKEN);
+        let game: GameState = fetch(&svm, &token_game_state_address(&mint));
+        assert!(game.status == GameStatus::Finished);
+        assert_eq!(game.prize, 0);
+    }
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn token_bid_equal_to_the_prize_is_rejected() {
+    for token_program in TOKEN_PROGRAMS {
+        let TokenGame {
+            mut svm,
+            token_program,
+            mint,
+            initial_king_tokens,
+            ..
+        } = setup_token_game(token_program);
+        let challenger = funded_wallet(&mut svm);
+        let challenger_tokens = create_token_account(
+            &mut svm,
+            &token_program,
+            &mint,
+            &challenger.pubkey(),
+            10 * TOKEN,
+        );
+
+        let game: GameState = fetch(&svm, &token_game_state_address(&mint));
+        let result = send(
+            &mut svm,
+            become_king_token(
+                &game,
+                &token_program,
+                &challenger.pubkey(),
+                &challenger_tokens,
+                &initial_king_tokens,
+                TOKEN,
+            ),
+            &challenger,
+        );
+
+        assert_error(result, ErrorCode::BidTooLow);
+    }
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn token_bid_must_beat_the_prize_after_the_transfer_fee() {
+    let mut svm = LiteSVM::new();
+    svm.add_program(
+        king_of_the_hill::ID,
+        &test_utils::program_binary("king_of_the_hill"),
+    )
+    .expect("the program should load");
+    let token_program = spl_token_2022::ID;
+    let mint = create_transfer_fee_mint(&mut svm);
+    let initial_king = funded_wallet(&mut svm);
+    let initial_king_tokens =
+        create_transfer_fee_token_account(&mut svm, &mint, &initial_king.pubkey(), 10 * TOKEN);
+    send(
+        &mut svm,
+        initialize_token_game(
+            &token_program,
+            &mint,
+            &initial_king.pubkey(),
+            &initial_king_tokens,
+            TOKEN,
+            0,
+        ),
+        &initial_king,
+    )
+    .unwrap();
+
+    // The fee is withheld from the stake, so the prize is what reached the pool
+    let pool = token_prize_pool_address(&mint);
+    let game: GameState = fetch(&svm, &token_game_state_address(&mint));
+    assert_eq!(game.prize, 900_000);
+    assert_eq!(token_balance(&svm, &pool), 900_000);
+
+    // A bid one above the prize only delivers 810_000 after the fee, less than the prize
+    let challenger = funded_wallet(&mut svm);
+    let challenger_tokens =
+        create_transfer_fee_token_account(&mut svm, &mint, &challenger.pubkey(), 10 * TOKEN);
+    let result = send(
+        &mut svm,
+        become_king_token(
+            &game,
+            &token_program,
+            &challenger.pubkey(),
+            &challenger_tokens,
+            &initial_king_tokens,
+            game.prize + 1,
+        ),
+        &challenger,
+    );
+    assert_error(result, ErrorCode::BidTooLow);
+    assert_eq!(token_balance(&svm, &challenger_tokens), 10 * TOKEN);
+
+    // A bid that still beats the prize after the fee goes through, and the prize is again
+    // what reached the pool
+    send(
+        &mut svm,
+        become_king_token(
+            &game,
+            &token_program,
+            &challenger.pubkey(),
+            &challenger_tokens,
+            &initial_king_tokens,
+            2 * TOKEN,
+        ),
+        &challenger,
+    )
+    .unwrap();
+    let game: GameState = fetch(&svm, &token_game_state_address(&mint));
+    assert_eq!(game.king, challenger.pubkey());
+    assert_eq!(game.prize, 1_800_000);
+    assert_eq!(token_balance(&svm, &pool), 1_800_000);
+}
+
+#[test]
+#[ignore = "needs target/deploy/king_of_the_hill.so, see the top of this file"]
+fn token_refund_into_a_spoofed_king_account_is_rejected() {
+    for token_program in TOKEN_PROGRAMS {
+        let TokenGame {
+            mut svm,
+            token_program,
+            mint,
+            ..
+        } = setup_token_game(token_program);
+        let attacker = funded_wallet(&mut svm);
+        let attacker_tokens = create_token_account(
+            &mut svm,
+            &token_program,
+            &mint,
+            &attacker.pubkey(),
+            10 * TOKEN,
+        );
+
+        // The attacker outbids the king and passes their own token account as the king's,
+        // to get the refund of the stake they are replacing
+        let game: GameState = fetch(&svm, &token_game_state_address(&mint));
+        let result = send(
+            &mut svm,
+            become_king_token(
+                &game,
+                &token_program,
+                &attacker.pubkey(),
+                &attacker_tokens,
+                &attacker_tokens,
+                2 * TOKEN,
+            ),
+            &attacker,
+        );
+
+        assert_error(result, anchor_lang::error::ErrorCode::ConstraintTokenOwner);
+    }
+}
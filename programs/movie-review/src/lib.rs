@@ -1,12 +1,47 @@
 use anchor_lang::prelude::*;
+use solana_sdk_ids::bpf_loader_upgradeable;
+use anchor_lang::solana_program::hash::hash;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{self, Mint, MintTo, TokenAccount, TokenInterface};
 
 declare_id!("7yNXF6wFHjHe2QANXwF9qFVyhUW6Th4NAEPzTgqNepcu");
 
 const MIN_RATING: u8 = 1;
 const MAX_RATING: u8 = 10;
-const MAX_TITLE_LENGTH: usize = 80;
-const MAX_DESCRIPTION_LENGTH: usize = 200;
+// Title and description limits are counted in characters, not bytes. A UTF-8 character takes
+// at most 4 bytes, so reserving that much per character keeps every valid string within the
+// space allocated for the account.
+pub const MAX_TITLE_LENGTH: usize = 80;
+pub const MAX_DESCRIPTION_LENGTH: usize = 200;
+pub const MAX_COMMENT_LENGTH: usize = 280;
+pub const MAX_DISPLAY_NAME_LENGTH: usize = 32;
+pub const MAX_TAGS: usize = 5;
+pub const MAX_TAG_LENGTH: usize = 20;
+const MAX_UTF8_BYTES_PER_CHAR: usize = 4;
+const MAX_TITLE_BYTES: usize = MAX_TITLE_LENGTH * MAX_UTF8_BYTES_PER_CHAR;
+const MAX_DESCRIPTION_BYTES: usize = MAX_DESCRIPTION_LENGTH * MAX_UTF8_BYTES_PER_CHAR;
+const MAX_COMMENT_BYTES: usize = MAX_COMMENT_LENGTH * MAX_UTF8_BYTES_PER_CHAR;
+const MAX_DISPLAY_NAME_BYTES: usize = MAX_DISPLAY_NAME_LENGTH * MAX_UTF8_BYTES_PER_CHAR;
+const MAX_TAG_BYTES: usize = MAX_TAG_LENGTH * MAX_UTF8_BYTES_PER_CHAR;
+// The year of the oldest surviving film. Release years can be up to a year in the future, so
+// upcoming movies can be reviewed after a screening.
+const FIRST_RELEASE_YEAR: u16 = 1888;
+const SECONDS_PER_YEAR: i64 = 31_556_952;
 const DISCRIMINATOR: usize = 8;
+const REWARD_MINT_DECIMALS: u8 = 6;
+pub const MAX_MODERATORS: usize = 10;
+// Reputation earned per review written and per like received on a review
+pub const REPUTATION_PER_REVIEW: u64 = 10;
+pub const REPUTATION_PER_LIKE: u64 = 5;
+
+/// Seed identifying a movie title. A raw title can't be used as a seed because Solana caps
+/// seeds at 32 bytes while titles may be up to `MAX_TITLE_BYTES` bytes long, so the title
+/// is lowercased and then hashed with SHA-256. It isn't trimmed, titles with surrounding
+/// whitespace are rejected instead. Clients must derive review addresses with this function
+/// (or an equivalent) to end up with the same PDA.
+pub fn title_seed(title: &str) -> [u8; 32] {
+    hash(title.to_lowercase().as_bytes()).to_bytes()
+}
 
 #[program]
 pub mod movie_review_program {
@@ -17,32 +52,80 @@ pub mod movie_review_program {
         title: String,
         description: String,
         rating: u8,
+        genre: Genre,
+        release_year: u16,
+        tags: Vec<String>,
     ) -> Result<()> {
-        require!(
-            rating >= MIN_RATING && rating <= MAX_RATING,
-            MovieReviewError::InvalidRating
-        );
-
-        require!(
-            title.len() <= MAX_TITLE_LENGTH,
-            MovieReviewError::TitleTooLong
-        );
-
-        require!(
-            description.len() <= MAX_DESCRIPTION_LENGTH,
-            MovieReviewError::DescriptionTooLong
-        );
+        validate_review(&title, &description, rating)?;
+        validate_metadata(release_year, &tags)?;
 
         let movie_review = &mut ctx.accounts.movie_review;
         movie_review.reviewer = ctx.accounts.reviewer.key();
+        movie_review.genre = genre;
+        movie_review.release_year = release_year;
         movie_review.title = title;
         movie_review.description = description;
         movie_review.rating = rating;
+        movie_review.tags = tags;
+        let now = Clock::get()?.unix_timestamp;
+        movie_review.created_at = now;
+        movie_review.updated_at = now;
+        movie_review.edit_count = 0;
+        movie_review.status = ReviewStatus::Visible;
+        movie_review.report_count = 0;
+        movie_review.like_count = 0;
+
+        let movie = &mut ctx.accounts.movie;
+        movie.bump = ctx.bumps.movie;
+        movie.add_rating(rating);
+
+        // The first review creates the reviewer's profile, with an empty display name
+        let profile = &mut ctx.accounts.reviewer_profile;
+        profile.authority = ctx.accounts.reviewer.key();
+        profile.bump = ctx.bumps.reviewer_profile;
+        profile.review_count += 1;
+        profile.update_reputation();
 
-        msg!("Movie review account created.");
-        msg!("Title: {}", movie_review.title);
-        msg!("Description: {}", movie_review.description);
-        msg!("Rating: {}", movie_review.rating);
+        // The profile is never closed, so no two reviews of a reviewer get the same nonce,
+        // even if they are written at the same address
+        movie_review.nonce = profile.reviews_created;
+        profile.reviews_created += 1;
+
+        // Reward the reviewer, once per title. The receipt is never closed, so deleting a
+        // review and writing it again doesn't pay a second reward.
+        let receipt = &mut ctx.accounts.reward_receipt;
+        if !receipt.is_issued() {
+            receipt.issue(ctx.accounts.reviewer.key(), ctx.bumps.reward_receipt);
+
+            // The mint authority is a PDA of this program, so only this instruction can mint
+            // reward tokens, signing for the PDA with its seeds.
+            let signer_seeds: &[&[&[u8]]] = &[&[
+                b"mint_authority",
+                &[ctx.accounts.reward_config.mint_authority_bump],
+            ]];
+            token_interface::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: ctx.accounts.reward_mint.to_account_info(),
+                        to: ctx.accounts.reviewer_token_account.to_account_info(),
+                        authority: ctx.accounts.mint_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                ctx.accounts.reward_config.reward_amount,
+            )?;
+        }
+
+        // Events are cheaper than formatting log messages and indexers can decode them with
+        // the program IDL
+        emit!(ReviewAdded {
+            review: movie_review.key(),
+            reviewer: movie_review.reviewer,
+            title: movie_review.title.clone(),
+            rating,
+            timestamp: now,
+        });
 
         Ok(())
     }
@@ -51,76 +134,516 @@ pub mod movie_review_program {
         ctx: Context<UpdateMovieReview>, 
         title: String, 
         description: String, 
-        rating: u8
+        rating: u8,
+        genre: Genre,
+        release_year: u16,
+        tags: Vec<String>,
     ) -> Result<()> {
+        validate_review(&title, &description, rating)?;
+        validate_metadata(release_year, &tags)?;
+
+        let movie_review = &mut ctx.accounts.movie_review;
+        let old_rating = movie_review.rating;
+        let movie = &mut ctx.accounts.movie;
+        movie.remove_rating(old_rating);
+        movie.add_rating(rating);
+
+        // The title matched the review's seed, so it can only differ from the stored title in
+        // case. Storing it lets reviewers fix capitalization, other changes need a rename.
+        movie_review.title = title;
+        movie_review.description = description;
+        movie_review.rating = rating;
+        movie_review.genre = genre;
+        movie_review.release_year = release_year;
+        movie_review.tags = tags;
+        movie_review.updated_at = Clock::get()?.unix_timestamp;
+        movie_review.edit_count += 1;
+
+        emit!(ReviewUpdated {
+            review: movie_review.key(),
+            reviewer: movie_review.reviewer,
+            title: movie_review.title.clone(),
+            old_rating,
+            new_rating: rating,
+            edit_count: movie_review.edit_count,
+            timestamp: movie_review.updated_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn delete_movie_review(ctx: Context<DeleteMovieReview>, title: String) -> Result<()> {
+        // The account closing is handled by the 'close' constraint in the DeleteMovieReview
+        // struct, we only need to take the rating out of the movie's aggregate.
+        let movie_review = &ctx.accounts.movie_review;
+        ctx.accounts.movie.remove_rating(movie_review.rating);
+
+        // Likes on the deleted review no longer count towards the reviewer's reputation
+        let profile = &mut ctx.accounts.reviewer_profile;
+        profile.review_count -= 1;
+        profile.likes_received -= movie_review.like_count;
+        profile.update_reputation();
+
+        emit!(ReviewDeleted {
+            review: movie_review.key(),
+            reviewer: movie_review.reviewer,
+            title,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn update_profile(ctx: Context<UpdateProfile>, display_name: String) -> Result<()> {
         require!(
-            rating >= MIN_RATING && rating <= MAX_RATING,
-            MovieReviewError::InvalidRating
+            display_name.chars().count() <= MAX_DISPLAY_NAME_LENGTH,
+            MovieReviewError::DisplayNameTooLong
         );
 
         require!(
-            title.len() <= MAX_TITLE_LENGTH,
-            MovieReviewError::TitleTooLong
+            !display_name.chars().any(char::is_control) && display_name.trim() == display_name,
+            MovieReviewError::InvalidDisplayName
         );
 
+        // Profiles are normally created by the first review, but a reviewer can pick a
+        // display name before writing one
+        let profile = &mut ctx.accounts.reviewer_profile;
+        profile.authority = ctx.accounts.authority.key();
+        profile.display_name = display_name;
+        profile.bump = ctx.bumps.reviewer_profile;
+
+        msg!("Profile of {} updated", profile.authority);
+        Ok(())
+    }
+
+    // Moves a review to the PDA of a new title, since the title is part of the review's
+    // address. Likes and comments are addressed by the old review and can't follow it, so
+    // reviews that have any can't be renamed. The moderation state carries over so hiding
+    // can't be undone by renaming.
+    pub fn rename_movie_review(
+        ctx: Context<RenameMovieReview>,
+        old_title: String,
+        new_title: String,
+    ) -> Result<()> {
+        let old_review = &ctx.accounts.old_review;
+        validate_review(&new_title, &old_review.description, old_review.rating)?;
+
+        require!(old_review.like_count == 0, MovieReviewError::ReviewHasLikes);
+        // A review that never had a comment has no counter
+        let counter_info = ctx.accounts.old_comment_counter.to_account_info();
+        if counter_info.owner == &crate::ID {
+            let counter =
+                CommentCounter::try_deserialize(&mut &counter_info.try_borrow_data()?[..])?;
+            // Open comments of an earlier review at the same address don't block the rename
+            require!(
+                counter.review_nonce != old_review.nonce || counter.open == 0,
+                MovieReviewError::ReviewHasComments
+            );
+        }
+
+        let mut review = MovieReviewData::clone(old_review);
+        review.title = new_title;
+        let now = Clock::get()?.unix_timestamp;
+        review.updated_at = now;
+        review.edit_count += 1;
+
+        ctx.accounts.old_movie.remove_rating(review.rating);
+        let new_movie = &mut ctx.accounts.new_movie;
+        new_movie.bump = ctx.bumps.new_movie;
+        new_movie.add_rating(review.rating);
+
+        // The review now stands for the new title, which can't be rewarded again after this
+        let receipt = &mut ctx.accounts.new_reward_receipt;
+        if !receipt.is_issued() {
+            receipt.issue(ctx.accounts.reviewer.key(), ctx.bumps.new_reward_receipt);
+        }
+
+        emit!(ReviewRenamed {
+            old_review: old_review.key(),
+            new_review: ctx.accounts.new_review.key(),
+            reviewer: review.reviewer,
+            old_title,
+            new_title: review.title.clone(),
+            timestamp: now,
+        });
+        ctx.accounts.new_review.set_inner(review);
+
+        // The old review is closed by the 'close' constraint in the RenameMovieReview struct
+        Ok(())
+    }
+
+    pub fn initialize_reward_mint(
+        ctx: Context<InitializeRewardMint>,
+        reward_amount: u64,
+    ) -> Result<()> {
+        // The upgrade authority creates the reward mint and becomes the admin, who can change
+        // the reward later
+        let reward_config = &mut ctx.accounts.reward_config;
+        reward_config.admin = ctx.accounts.admin.key();
+        reward_config.reward_mint = ctx.accounts.reward_mint.key();
+        reward_config.reward_amount = reward_amount;
+        reward_config.bump = ctx.bumps.reward_config;
+        reward_config.mint_authority_bump = ctx.bumps.mint_authority;
+
+        msg!("Reward mint {} created", reward_config.reward_mint);
+        Ok(())
+    }
+
+    pub fn set_reward_amount(ctx: Context<SetRewardAmount>, reward_amount: u64) -> Result<()> {
+        ctx.accounts.reward_config.reward_amount = reward_amount;
+
+        msg!("Reward set to {} per review", reward_amount);
+        Ok(())
+    }
+
+    pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+        // The upgrade authority creates the config and becomes the admin, the moderator list
+        // starts empty
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.moderators = Vec::new();
+        config.bump = ctx.bumps.config;
+
+        msg!("Config created with admin {}", config.admin);
+        Ok(())
+    }
+
+    pub fn add_moderator(ctx: Context<ManageModerators>, moderator: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
         require!(
-            description.len() <= MAX_DESCRIPTION_LENGTH,
-            MovieReviewError::DescriptionTooLong
+            !config.moderators.contains(&moderator),
+            MovieReviewError::AlreadyModerator
         );
+        require!(
+            config.moderators.len() < MAX_MODERATORS,
+            MovieReviewError::TooManyModerators
+        );
+        config.moderators.push(moderator);
+
+        msg!("Moderator {} added", moderator);
+        Ok(())
+    }
+
+    pub fn remove_moderator(ctx: Context<ManageModerators>, moderator: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let position = config
+            .moderators
+            .iter()
+            .position(|m| *m == moderator)
+            .ok_or(MovieReviewError::NotModerator)?;
+        config.moderators.remove(position);
+
+        msg!("Moderator {} removed", moderator);
+        Ok(())
+    }
+
+    // Anyone can report a review, moderators use the report count to decide what to look at.
+    // The report marker can only be created once per reporter, so a second report fails when
+    // initializing it.
+    pub fn report_review(ctx: Context<ReportReview>) -> Result<()> {
+        let report = &mut ctx.accounts.report;
+        report.review = ctx.accounts.movie_review.key();
+        report.reporter = ctx.accounts.reporter.key();
+        report.bump = ctx.bumps.report;
 
         let movie_review = &mut ctx.accounts.movie_review;
-        movie_review.description = description;
-        movie_review.rating = rating;
+        movie_review.report_count += 1;
+
+        msg!("Review {} reported {} times", movie_review.key(), movie_review.report_count);
+        Ok(())
+    }
+
+    pub fn hide_review(ctx: Context<ModerateReview>) -> Result<()> {
+        let movie_review = &mut ctx.accounts.movie_review;
+        require!(
+            movie_review.status != ReviewStatus::Hidden,
+            MovieReviewError::ReviewAlreadyHidden
+        );
+        movie_review.status = ReviewStatus::Hidden;
 
-        msg!("Movie review updated.");
-        msg!("Title: {}", movie_review.title);
-        msg!("Description: {}", movie_review.description);
-        msg!("Rating: {}", movie_review.rating);
+        msg!("Review {} hidden", movie_review.key());
+        Ok(())
+    }
+
+    pub fn unhide_review(ctx: Context<ModerateReview>) -> Result<()> {
+        let movie_review = &mut ctx.accounts.movie_review;
+        require!(
+            movie_review.status == ReviewStatus::Hidden,
+            MovieReviewError::ReviewNotHidden
+        );
+        movie_review.status = ReviewStatus::Visible;
 
+        msg!("Review {} unhidden", movie_review.key());
         Ok(())
     }
 
-    pub fn delete_movie_review(_ctx: Context<DeleteMovieReview>, title: String) -> Result<()> {
-        // Because we are closing the account we don't need any instruction logic inside the body
-        // of the function. The account closing will be handled by the 'close' constraint in the
-        // DeleteMovieReview struct.
-        msg!("Movie review for {} deleted", title);
+    pub fn like_review(ctx: Context<LikeReview>) -> Result<()> {
+        // The like marker can only be created once per voter, so a second like fails when
+        // initializing it
+        let like = &mut ctx.accounts.like;
+        like.review = ctx.accounts.movie_review.key();
+        like.voter = ctx.accounts.voter.key();
+        like.reviewer = ctx.accounts.movie_review.reviewer;
+        like.review_nonce = ctx.accounts.movie_review.nonce;
+        like.bump = ctx.bumps.like;
+
+        let movie_review = &mut ctx.accounts.movie_review;
+        movie_review.like_count += 1;
+
+        let profile = &mut ctx.accounts.reviewer_profile;
+        profile.likes_received += 1;
+        profile.update_reputation();
+
+        msg!("Review {} has {} likes", movie_review.key(), movie_review.like_count);
         Ok(())
     }
 
+    pub fn unlike_review(ctx: Context<UnlikeReview>) -> Result<()> {
+        // The 'close' constraint removes the like marker and refunds its rent to the voter.
+        // The review may have been deleted since it was liked, possibly followed by a new
+        // review at the same address. Deleting a review already took its likes off the
+        // reviewer's reputation, so the counts only change if the liked review still exists.
+        let review_info = ctx.accounts.movie_review.to_account_info();
+        let Some(mut movie_review) = read_review(&review_info)? else {
+            msg!("Like of deleted review {} removed", review_info.key());
+            return Ok(());
+        };
+        if movie_review.nonce != ctx.accounts.like.review_nonce {
+            msg!("Like of deleted review {} removed", review_info.key());
+            return Ok(());
+        }
+
+        movie_review.like_count = movie_review
+            .like_count
+            .checked_sub(1)
+            .ok_or(MovieReviewError::LikeCountUnderflow)?;
+        // Anchor only writes back accounts it deserialized itself
+        let mut data = review_info.try_borrow_mut_data()?;
+        movie_review.try_serialize(&mut data.as_mut())?;
+
+        let profile = &mut ctx.accounts.reviewer_profile;
+        profile.likes_received = profile
+            .likes_received
+            .checked_sub(1)
+            .ok_or(MovieReviewError::LikeCountUnderflow)?;
+        profile.update_reputation();
+
+        msg!("Review {} has {} likes", review_info.key(), movie_review.like_count);
+        Ok(())
+    }
+
+    pub fn add_comment(ctx: Context<AddComment>, body: String) -> Result<()> {
+        require!(!body.is_empty(), MovieReviewError::EmptyComment);
+
+        require!(
+            body.chars().count() <= MAX_COMMENT_LENGTH,
+            MovieReviewError::CommentTooLong
+        );
+
+        require!(
+            !body.chars().any(|c| c.is_control() && c != '\n'),
+            MovieReviewError::CommentContainsControlCharacter
+        );
+
+        // The comment was created at the counter's current value, so the next comment gets
+        // the following index. Indexes are never reused, even after a comment is deleted.
+        let comment_counter = &mut ctx.accounts.comment_counter;
+        let index = comment_counter.count;
+        comment_counter.count += 1;
+        comment_counter.bump = ctx.bumps.comment_counter;
+        // The counter outlives the review, so its open comments may belong to a deleted
+        // review that was at the same address. They no longer count against this one.
+        let review_nonce = ctx.accounts.movie_review.nonce;
+        if comment_counter.review_nonce != review_nonce {
+            comment_counter.review_nonce = review_nonce;
+            comment_counter.open = 0;
+        }
+        comment_counter.open += 1;
+
+        let comment = &mut ctx.accounts.comment;
+        comment.review = ctx.accounts.movie_review.key();
+        comment.review_nonce = review_nonce;
+        comment.author = ctx.accounts.author.key();
+        comment.index = index;
+        comment.body = body;
+        comment.bump = ctx.bumps.comment;
+
+        msg!("Comment {} added to review {}", index, comment.review);
+        Ok(())
+    }
+
+    pub fn delete_comment(ctx: Context<DeleteComment>) -> Result<()> {
+        // Only the author can get past the `has_one` constraint, and the 'close' constraint
+        // returns the rent to them. Comments of a deleted review were already dropped from
+        // the open count when a new review at the same address got its first comment.
+        let comment_counter = &mut ctx.accounts.comment_counter;
+        if comment_counter.review_nonce == ctx.accounts.comment.review_nonce {
+            comment_counter.open -= 1;
+        }
+
+        let comment = &ctx.accounts.comment;
+        msg!("Comment {} deleted from review {}", comment.index, comment.review);
+        Ok(())
+    }
+
+}
+
+fn validate_review(title: &str, description: &str, rating: u8) -> Result<()> {
+    require!(
+        (MIN_RATING..=MAX_RATING).contains(&rating),
+        MovieReviewError::InvalidRating
+    );
+
+    require!(!title.is_empty(), MovieReviewError::EmptyTitle);
+
+    require!(
+        title.chars().count() <= MAX_TITLE_LENGTH,
+        MovieReviewError::TitleTooLong
+    );
+
+    require!(
+        !title.chars().any(char::is_control),
+        MovieReviewError::TitleContainsControlCharacter
+    );
+
+    // Otherwise " Inception" and "Inception" would hash to different seeds and split the
+    // reviews and the rating of one movie across two addresses.
+    require!(
+        title.trim() == title,
+        MovieReviewError::TitleHasSurroundingWhitespace
+    );
+
+    require!(
+        description.chars().count() <= MAX_DESCRIPTION_LENGTH,
+        MovieReviewError::DescriptionTooLong
+    );
+
+    // Line breaks are allowed in descriptions, any other control character is not
+    require!(
+        !description.chars().any(|c| c.is_control() && c != '\n'),
+        MovieReviewError::DescriptionContainsControlCharacter
+    );
+
+    Ok(())
+}
+
+// Reads a review that Anchor didn't deserialize, because it may no longer exist. Returns
+// None if the account was closed.
+fn read_review(account: &AccountInfo) -> Result<Option<MovieReviewData>> {
+    if account.owner != &crate::ID || account.data_is_empty() {
+        return Ok(None);
+    }
+    // Checks the discriminator, so no other account of this program passes as a review
+    let review = MovieReviewData::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    Ok(Some(review))
+}
+
+fn validate_metadata(release_year: u16, tags: &[String]) -> Result<()> {
+    let current_year = 1970 + Clock::get()?.unix_timestamp / SECONDS_PER_YEAR;
+    require!(
+        release_year >= FIRST_RELEASE_YEAR && i64::from(release_year) <= current_year + 1,
+        MovieReviewError::InvalidReleaseYear
+    );
+
+    require!(tags.len() <= MAX_TAGS, MovieReviewError::TooManyTags);
+
+    for tag in tags {
+        require!(
+            tag.chars().count() <= MAX_TAG_LENGTH,
+            MovieReviewError::TagTooLong
+        );
+
+        // Tags are single words, so whitespace is rejected along with other control characters
+        require!(
+            !tag.is_empty() && !tag.chars().any(|c| c.is_control() || c.is_whitespace()),
+            MovieReviewError::InvalidTag
+        );
+    }
+
+    Ok(())
 }
 
 #[derive(Accounts)]
-#[instruction(title: String)]
+#[instruction(title: String, description: String, rating: u8, genre: Genre, release_year: u16, tags: Vec<String>)]
 pub struct AddMovieReview<'info> {
     #[account(
         init, 
-        seeds = [title.as_bytes(), reviewer.key().as_ref()], 
+        seeds = [title_seed(&title).as_ref(), reviewer.key().as_ref()], 
         bump, 
         payer = reviewer, 
-        space = DISCRIMINATOR + MovieReviewData::INIT_SPACE,
+        // Only pay rent for the text the review actually holds
+        space = MovieReviewData::space(&title, &description, &tags),
     )]
     pub movie_review: Account<'info, MovieReviewData>,
+    // The first review of a movie creates its aggregate account
+    #[account(
+        init_if_needed,
+        seeds = [b"movie", title_seed(&title).as_ref()],
+        bump,
+        payer = reviewer,
+        space = DISCRIMINATOR + Movie::INIT_SPACE,
+    )]
+    pub movie: Account<'info, Movie>,
+    // Created with the first review of this title by the reviewer, and kept afterwards
+    #[account(
+        init_if_needed,
+        seeds = [b"rewarded", title_seed(&title).as_ref(), reviewer.key().as_ref()],
+        bump,
+        payer = reviewer,
+        space = DISCRIMINATOR + RewardReceipt::INIT_SPACE,
+    )]
+    pub reward_receipt: Box<Account<'info, RewardReceipt>>,
+    #[account(seeds = [b"reward_config"], bump = reward_config.bump, has_one = reward_mint)]
+    pub reward_config: Account<'info, RewardConfig>,
+    #[account(mut)]
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: only used as the signing mint authority, verified by its seeds
+    #[account(seeds = [b"mint_authority"], bump = reward_config.mint_authority_bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    // Created on the reviewer's first review
+    #[account(
+        init_if_needed,
+        payer = reviewer,
+        associated_token::mint = reward_mint,
+        associated_token::authority = reviewer,
+        associated_token::token_program = token_program,
+    )]
+    pub reviewer_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        seeds = [b"profile", reviewer.key().as_ref()],
+        bump,
+        payer = reviewer,
+        space = DISCRIMINATOR + ReviewerProfile::INIT_SPACE,
+    )]
+    pub reviewer_profile: Box<Account<'info, ReviewerProfile>>,
     #[account(mut)]
     pub reviewer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
-
 }
 
 #[derive(Accounts)]
-#[instruction(title: String)]
+#[instruction(title: String, description: String, rating: u8, genre: Genre, release_year: u16, tags: Vec<String>)]
 pub struct UpdateMovieReview<'info> {
     #[account(
         mut,
-        seeds = [title.as_bytes(), reviewer.key().as_ref()],
+        seeds = [title_seed(&title).as_ref(), reviewer.key().as_ref()],
         bump,
-        realloc = DISCRIMINATOR + MovieReviewData::INIT_SPACE,
+        realloc = MovieReviewData::space(&title, &description, &tags),
         // Set the reviewer as the payer for any additional lamports required for rent 
-        // exemption in case the new description is larger than the previous one.
+        // exemption in case the new description is larger than the previous one. If it is
+        // smaller, the lamports no longer needed for rent are refunded to the reviewer.
         realloc::payer = reviewer,
         realloc::zero = true,
     )]
     pub movie_review: Account<'info, MovieReviewData>,
+    #[account(
+        mut,
+        seeds = [b"movie", title_seed(&title).as_ref()],
+        bump = movie.bump,
+    )]
+    pub movie: Account<'info, Movie>,
     #[account(mut)]
     pub reviewer: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -131,33 +654,611 @@ pub struct UpdateMovieReview<'info> {
 pub struct DeleteMovieReview<'info> {
     #[account(
         mut,
-        seeds = [title.as_bytes(), reviewer.key().as_ref()],
+        seeds = [title_seed(&title).as_ref(), reviewer.key().as_ref()],
         bump,
         close = reviewer
     )]
     pub movie_review: Account<'info, MovieReviewData>,
+    #[account(
+        mut,
+        seeds = [b"movie", title_seed(&title).as_ref()],
+        bump = movie.bump,
+    )]
+    pub movie: Account<'info, Movie>,
+    #[account(
+        mut,
+        seeds = [b"profile", reviewer.key().as_ref()],
+        bump = reviewer_profile.bump,
+    )]
+    pub reviewer_profile: Account<'info, ReviewerProfile>,
     #[account(mut)]
     pub reviewer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(old_title: String, new_title: String)]
+pub struct RenameMovieReview<'info> {
+    #[account(
+        mut,
+        seeds = [title_seed(&old_title).as_ref(), reviewer.key().as_ref()],
+        bump,
+        close = reviewer,
+    )]
+    pub old_review: Box<Account<'info, MovieReviewData>>,
+    // `init` fails if the reviewer already has a review under the new title, so renaming can
+    // never overwrite another review
+    #[account(
+        init,
+        seeds = [title_seed(&new_title).as_ref(), reviewer.key().as_ref()],
+        bump,
+        payer = reviewer,
+        space = MovieReviewData::space(&new_title, &old_review.description, &old_review.tags),
+    )]
+    pub new_review: Box<Account<'info, MovieReviewData>>,
+    #[account(
+        mut,
+        seeds = [b"movie", title_seed(&old_title).as_ref()],
+        bump = old_movie.bump,
+    )]
+    pub old_movie: Box<Account<'info, Movie>>,
+    #[account(
+        init_if_needed,
+        seeds = [b"movie", title_seed(&new_title).as_ref()],
+        bump,
+        payer = reviewer,
+        space = DISCRIMINATOR + Movie::INIT_SPACE,
+    )]
+    pub new_movie: Box<Account<'info, Movie>>,
+    #[account(
+        init_if_needed,
+        seeds = [b"rewarded", title_seed(&new_title).as_ref(), reviewer.key().as_ref()],
+        bump,
+        payer = reviewer,
+        space = DISCRIMINATOR + RewardReceipt::INIT_SPACE,
+    )]
+    pub new_reward_receipt: Box<Account<'info, RewardReceipt>>,
+    /// CHECK: the comment counter of the old review, which doesn't exist until its first
+    /// comment. It is read by rename_movie_review.
+    #[account(seeds = [b"comment_counter", old_review.key().as_ref()], bump)]
+    pub old_comment_counter: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub reviewer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Fixed size fields come first so they are at the same offset in every review, which lets
+// clients filter reviews with `memcmp` on getProgramAccounts. Variable length fields go last.
 #[account]
 #[derive(InitSpace)]
 pub struct MovieReviewData {
-    pub reviewer: Pubkey, // 32 bytes
-    #[max_len(MAX_TITLE_LENGTH)]
+    pub reviewer: Pubkey,     // 32 bytes
+    pub genre: Genre,         // 1 byte
+    pub release_year: u16,    // 2 bytes
+    pub rating: u8,           // 1 byte
+    pub created_at: i64,      // 8 bytes, unix timestamp
+    pub updated_at: i64,      // 8 bytes, unix timestamp of the last update
+    pub edit_count: u32,      // 4 bytes
+    pub status: ReviewStatus, // 1 byte
+    pub report_count: u32,    // 4 bytes
+    pub like_count: u64,      // 8 bytes
+    // Sets the review apart from earlier reviews at the same address, see add_movie_review
+    pub nonce: u64,           // 8 bytes
+    #[max_len(MAX_TITLE_BYTES)]
     pub title: String, // 4 + len()
-    #[max_len(MAX_DESCRIPTION_LENGTH)]
+    #[max_len(MAX_DESCRIPTION_BYTES)]
     pub description: String, // 4 + len()
-    pub rating: u8,     // 1 byte
+    #[max_len(MAX_TAGS, MAX_TAG_BYTES)]
+    pub tags: Vec<String>, // 4 + (4 + len()) per tag
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum Genre {
+    Action,
+    Animation,
+    Comedy,
+    Documentary,
+    Drama,
+    Fantasy,
+    Horror,
+    Romance,
+    ScienceFiction,
+    Thriller,
+    Other,
+}
+
+// Enums stored in accounts are serialized as a single byte with the variant index
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum ReviewStatus {
+    Visible,
+    // Hidden by a moderator, clients should not show the review
+    Hidden,
+}
+
+impl MovieReviewData {
+    // Offsets of the fixed size fields in the account data, for `memcmp` filters
+    pub const REVIEWER_OFFSET: usize = DISCRIMINATOR;
+    pub const GENRE_OFFSET: usize = Self::REVIEWER_OFFSET + 32;
+    pub const RELEASE_YEAR_OFFSET: usize = Self::GENRE_OFFSET + 1;
+
+    // Account size for a review holding exactly this title, description and tags, instead of
+    // the maximum lengths that INIT_SPACE reserves
+    pub fn space(title: &str, description: &str, tags: &[String]) -> usize {
+        DISCRIMINATOR + Self::INIT_SPACE
+            - MAX_TITLE_BYTES
+            - MAX_DESCRIPTION_BYTES
+            - MAX_TAGS * (4 + MAX_TAG_BYTES)
+            + title.len()
+            + description.len()
+            + tags.iter().map(|tag| 4 + tag.len()).sum::<usize>()
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.status == ReviewStatus::Hidden
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateProfile<'info> {
+    #[account(
+        init_if_needed,
+        seeds = [b"profile", authority.key().as_ref()],
+        bump,
+        payer = authority,
+        space = DISCRIMINATOR + ReviewerProfile::INIT_SPACE,
+    )]
+    pub reviewer_profile: Account<'info, ReviewerProfile>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRewardMint<'info> {
+    #[account(
+        init,
+        seeds = [b"reward_config"],
+        bump,
+        payer = admin,
+        space = DISCRIMINATOR + RewardConfig::INIT_SPACE,
+    )]
+    pub reward_config: Account<'info, RewardConfig>,
+    #[account(
+        init,
+        seeds = [b"reward_mint"],
+        bump,
+        payer = admin,
+        mint::decimals = REWARD_MINT_DECIMALS,
+        mint::authority = mint_authority,
+        mint::token_program = token_program,
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: a PDA that never holds data, it only signs as the mint authority
+    #[account(seeds = [b"mint_authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+    // The reward mint can only be created once, so it is reserved for whoever can upgrade
+    // the program. Otherwise anyone could create it first, right after a deployment, and
+    // control the rewards.
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ MovieReviewError::NotUpgradeAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardAmount<'info> {
+    #[account(mut, seeds = [b"reward_config"], bump = reward_config.bump, has_one = admin)]
+    pub reward_config: Account<'info, RewardConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        seeds = [b"config"],
+        bump,
+        payer = admin,
+        space = DISCRIMINATOR + Config::INIT_SPACE,
+    )]
+    pub config: Account<'info, Config>,
+    // Same check as in InitializeRewardMint, the admin picks the moderators
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ MovieReviewError::NotUpgradeAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageModerators<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReportReview<'info> {
+    // Reviews are passed by address, a reporter doesn't need to know the title or reviewer
+    #[account(mut)]
+    pub movie_review: Account<'info, MovieReviewData>,
+    #[account(
+        init,
+        seeds = [b"report", movie_review.key().as_ref(), reporter.key().as_ref()],
+        bump,
+        payer = reporter,
+        space = DISCRIMINATOR + Report::INIT_SPACE,
+    )]
+    pub report: Account<'info, Report>,
+    #[account(mut)]
+    pub reporter: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ModerateReview<'info> {
+    #[account(mut)]
+    pub movie_review: Account<'info, MovieReviewData>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.moderators.contains(&moderator.key()) @ MovieReviewError::NotModerator,
+    )]
+    pub config: Account<'info, Config>,
+    pub moderator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LikeReview<'info> {
+    #[account(
+        mut,
+        constraint = movie_review.reviewer != voter.key() @ MovieReviewError::CannotLikeOwnReview,
+    )]
+    pub movie_review: Account<'info, MovieReviewData>,
+    #[account(
+        init,
+        seeds = [b"like", movie_review.key().as_ref(), voter.key().as_ref()],
+        bump,
+        payer = voter,
+        space = DISCRIMINATOR + Like::INIT_SPACE,
+    )]
+    pub like: Account<'info, Like>,
+    // Likes count towards the reputation of the review's author, not the voter
+    #[account(
+        mut,
+        seeds = [b"profile", movie_review.reviewer.as_ref()],
+        bump = reviewer_profile.bump,
+    )]
+    pub reviewer_profile: Account<'info, ReviewerProfile>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnlikeReview<'info> {
+    /// CHECK: the review the like was given to, which may have been deleted since. It is
+    /// read by unlike_review, which checks that it is still a review.
+    #[account(mut, address = like.review)]
+    pub movie_review: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"like", movie_review.key().as_ref(), voter.key().as_ref()],
+        bump = like.bump,
+        close = voter,
+    )]
+    pub like: Account<'info, Like>,
+    // Profiles are never closed, so it exists even if the review doesn't
+    #[account(
+        mut,
+        seeds = [b"profile", like.reviewer.as_ref()],
+        bump = reviewer_profile.bump,
+    )]
+    pub reviewer_profile: Account<'info, ReviewerProfile>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddComment<'info> {
+    pub movie_review: Account<'info, MovieReviewData>,
+    #[account(
+        init_if_needed,
+        seeds = [b"comment_counter", movie_review.key().as_ref()],
+        bump,
+        payer = author,
+        space = DISCRIMINATOR + CommentCounter::INIT_SPACE,
+    )]
+    pub comment_counter: Account<'info, CommentCounter>,
+    #[account(
+        init,
+        seeds = [
+            b"comment",
+            movie_review.key().as_ref(),
+            comment_counter.count.to_le_bytes().as_ref(),
+        ],
+        bump,
+        payer = author,
+        space = DISCRIMINATOR + Comment::INIT_SPACE,
+    )]
+    pub comment: Account<'info, Comment>,
+    #[account(mut)]
+    pub author: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeleteComment<'info> {
+    // The review isn't needed here, so comments can still be cleaned up after the review
+    // they belong to has been deleted
+    #[account(
+        mut,
+        seeds = [b"comment", comment.review.as_ref(), comment.index.to_le_bytes().as_ref()],
+        bump = comment.bump,
+        has_one = author @ MovieReviewError::NotCommentAuthor,
+        close = author,
+    )]
+    pub comment: Account<'info, Comment>,
+    // Counters are never closed, so it exists as long as any comment of the review does
+    #[account(
+        mut,
+        seeds = [b"comment_counter", comment.review.as_ref()],
+        bump = comment_counter.bump,
+    )]
+    pub comment_counter: Account<'info, CommentCounter>,
+    #[account(mut)]
+    pub author: Signer<'info>,
+}
+
+// Aggregate of every review for a movie, so the average rating can be read from a single
+// account instead of scanning all reviews
+#[account]
+#[derive(InitSpace)]
+pub struct Movie {
+    pub review_count: u64, // 8 bytes
+    pub rating_sum: u64,   // 8 bytes
+    // Number of reviews per rating, index 0 counts ratings of 1
+    pub rating_histogram: [u64; MAX_RATING as usize], // 8 * 10 bytes
+    pub bump: u8,          // 1 byte
+}
+
+impl Movie {
+    fn add_rating(&mut self, rating: u8) {
+        self.review_count += 1;
+        self.rating_sum += u64::from(rating);
+        self.rating_histogram[usize::from(rating - MIN_RATING)] += 1;
+    }
+
+    fn remove_rating(&mut self, rating: u8) {
+        self.review_count -= 1;
+        self.rating_sum -= u64::from(rating);
+        self.rating_histogram[usize::from(rating - MIN_RATING)] -= 1;
+    }
+
+    pub fn average_rating(&self) -> Option<f64> {
+        (self.review_count > 0).then(|| self.rating_sum as f64 / self.review_count as f64)
+    }
+}
+
+// Credibility of a reviewer, kept up to date by the review and like instructions
+#[account]
+#[derive(InitSpace)]
+pub struct ReviewerProfile {
+    pub authority: Pubkey, // 32 bytes
+    #[max_len(MAX_DISPLAY_NAME_BYTES)]
+    pub display_name: String, // 4 + len()
+    pub review_count: u64, // 8 bytes
+    pub likes_received: u64, // 8 bytes
+    pub reputation: u64,   // 8 bytes
+    // Reviews ever added, unlike review_count it doesn't go down when one is deleted. Used
+    // as the nonce of the next review.
+    pub reviews_created: u64, // 8 bytes
+    pub bump: u8,          // 1 byte
+}
+
+impl ReviewerProfile {
+    fn update_reputation(&mut self) {
+        self.reputation = self.review_count * REPUTATION_PER_REVIEW
+            + self.likes_received * REPUTATION_PER_LIKE;
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey, // 32 bytes
+    #[max_len(MAX_MODERATORS)]
+    pub moderators: Vec<Pubkey>, // 4 + 32 * len()
+    pub bump: u8,      // 1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RewardConfig {
+    pub admin: Pubkey,           // 32 bytes
+    pub reward_mint: Pubkey,     // 32 bytes
+    pub reward_amount: u64,      // 8 bytes, in base units of the reward mint
+    pub bump: u8,                // 1 byte
+    pub mint_authority_bump: u8, // 1 byte
+}
+
+// Marks that a reviewer has been rewarded for reviewing a title. It outlives the review, so
+// there is at most one reward per reviewer and title.
+#[account]
+#[derive(InitSpace)]
+pub struct RewardReceipt {
+    pub reviewer: Pubkey, // 32 bytes
+    pub bump: u8,         // 1 byte
+}
+
+impl RewardReceipt {
+    // Accounts created by init_if_needed are zeroed, so a receipt that was just created
+    // has no reviewer yet
+    fn is_issued(&self) -> bool {
+        self.reviewer != Pubkey::default()
+    }
+
+    fn issue(&mut self, reviewer: Pubkey, bump: u8) {
+        self.reviewer = reviewer;
+        self.bump = bump;
+    }
+}
+
+// Marks that a voter liked a review, its address can only exist once per voter and review
+#[account]
+#[derive(InitSpace)]
+pub struct Like {
+    pub review: Pubkey,   // 32 bytes
+    pub voter: Pubkey,    // 32 bytes
+    pub reviewer: Pubkey, // 32 bytes, author of the review
+    // Nonce of the liked review, which tells it apart from a later review at the same address
+    pub review_nonce: u64, // 8 bytes
+    pub bump: u8,         // 1 byte
+}
+
+// Marks that a reporter reported a review, so each reporter counts once in report_count
+#[account]
+#[derive(InitSpace)]
+pub struct Report {
+    pub review: Pubkey,   // 32 bytes
+    pub reporter: Pubkey, // 32 bytes
+    pub bump: u8,         // 1 byte
+}
+
+// Number of comments ever added at a review address, used to address the next comment, and
+// of the comments of the current review there that haven't been deleted
+#[account]
+#[derive(InitSpace)]
+pub struct CommentCounter {
+    pub count: u64,        // 8 bytes
+    pub open: u64,         // 8 bytes
+    pub review_nonce: u64, // 8 bytes, nonce of the review `open` counts comments of
+    pub bump: u8,          // 1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Comment {
+    pub review: Pubkey, // 32 bytes
+    pub review_nonce: u64, // 8 bytes
+    pub author: Pubkey, // 32 bytes
+    pub index: u64,     // 8 bytes
+    #[max_len(MAX_COMMENT_BYTES)]
+    pub body: String,   // 4 + len()
+    pub bump: u8,       // 1 byte
+}
+
+// Events are not stored in any account. They are written to the transaction logs and can be
+// decoded by clients with the program IDL.
+#[event]
+pub struct ReviewAdded {
+    pub review: Pubkey,
+    pub reviewer: Pubkey,
+    pub title: String,
+    pub rating: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReviewUpdated {
+    pub review: Pubkey,
+    pub reviewer: Pubkey,
+    pub title: String,
+    pub old_rating: u8,
+    pub new_rating: u8,
+    pub edit_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReviewDeleted {
+    pub review: Pubkey,
+    pub reviewer: Pubkey,
+    // The title as passed to the instruction, which may differ from the stored one in case
+    pub title: String,
+    pub timestamp: i64,
+}
+
+// A rename moves the review to the address of the new title, closing the old one
+#[event]
+pub struct ReviewRenamed {
+    pub old_review: Pubkey,
+    pub new_review: Pubkey,
+    pub reviewer: Pubkey,
+    // The old title as passed to the instruction, see ReviewDeleted
+    pub old_title: String,
+    pub new_title: String,
+    pub timestamp: i64,
 }
 
 #[error_code]
-enum MovieReviewError {
+pub enum MovieReviewError {
     #[msg("Rating must be between 1 and 10")]
     InvalidRating,
     #[msg("Movie title too long")]
     TitleTooLong,
     #[msg("Description too long")]
     DescriptionTooLong,
+    #[msg("Movie title cannot be empty")]
+    EmptyTitle,
+    #[msg("Movie title cannot contain control characters")]
+    TitleContainsControlCharacter,
+    #[msg("Movie title cannot start or end with whitespace")]
+    TitleHasSurroundingWhitespace,
+    #[msg("Description cannot contain control characters other than line breaks")]
+    DescriptionContainsControlCharacter,
+    #[msg("Comment cannot be empty")]
+    EmptyComment,
+    #[msg("Comment too long")]
+    CommentTooLong,
+    #[msg("Comment cannot contain control characters other than line breaks")]
+    CommentContainsControlCharacter,
+    #[msg("Only the author can delete a comment")]
+    NotCommentAuthor,
+    #[msg("Signer is not a moderator")]
+    NotModerator,
+    #[msg("Account is already a moderator")]
+    AlreadyModerator,
+    #[msg("Moderator list is full")]
+    TooManyModerators,
+    #[msg("Review is already hidden")]
+    ReviewAlreadyHidden,
+    #[msg("Review is not hidden")]
+    ReviewNotHidden,
+    #[msg("Reviewers cannot like their own review")]
+    CannotLikeOwnReview,
+    #[msg("Display name too long")]
+    DisplayNameTooLong,
+    #[msg("Display name cannot contain control characters or start or end with whitespace")]
+    InvalidDisplayName,
+    #[msg("Release year is out of range")]
+    InvalidReleaseYear,
+    #[msg("Too many tags")]
+    TooManyTags,
+    #[msg("Tag too long")]
+    TagTooLong,
+    #[msg("Tags cannot be empty or contain whitespace or control characters")]
+    InvalidTag,
+    #[msg("Only the upgrade authority of the program can do this")]
+    NotUpgradeAuthority,
+    #[msg("Like count cannot go below zero")]
+    LikeCountUnderflow,
+    #[msg("Reviews with likes cannot be renamed")]
+    ReviewHasLikes,
+    #[msg("Reviews with comments cannot be renamed")]
+    ReviewHasComments,
 }
@@ -0,0 +1,1193 @@
+// Tests of the movie review program against its compiled binary, covering the instructions
+// one by one and, with proptest, the text limits on arbitrary input. They need
+// target/deploy/movie_review.so, so they are ignored by plain `cargo test`. Run them with
+// `yarn test:rust:movie-review`, which builds the program first.
+use anchor_lang::{AccountSerialize, Space};
+use anchor_spl::token;
+use clients::movie_review::{
+    add_comment, add_moderator, add_movie_review, comment_address, comment_counter_address,
+    config_address, delete_comment, delete_movie_review, hide_review, initialize_config,
+    initialize_reward_mint, like_address, like_review, movie_address, profile_address,
+    remove_moderator, rename_movie_review, report_review, review_address, reviewer_token_account,
+    reward_config_address, reward_receipt_address, unhide_review, unlike_review,
+    update_movie_review, update_profile, ReviewInput,
+};
+use litesvm::LiteSVM;
+use movie_review::{
+    Comment, CommentCounter, Config, Genre, Like, Movie, MovieReviewData, MovieReviewError,
+    ReviewStatus, ReviewerProfile, RewardConfig, MAX_COMMENT_LENGTH, MAX_DESCRIPTION_LENGTH,
+    MAX_DISPLAY_NAME_LENGTH, MAX_MODERATORS, MAX_TAGS, MAX_TAG_LENGTH, MAX_TITLE_LENGTH,
+    REPUTATION_PER_LIKE, REPUTATION_PER_REVIEW,
+};
+use proptest::{
+    prelude::*,
+    test_runner::{TestCaseError, TestRunner},
+};
+use solana_sdk::{
+    clock::Clock,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use test_utils::{
+    assert_error, balance, fetch, funded_wallet, is_closed, program_error, send, SIGNATURE_FEE,
+};
+
+const REWARD_AMOUNT: u64 = 5_000_000;
+// 2026-01-01, LiteSVM starts its clock at the unix epoch which is before any release year
+const NOW: i64 = 1_767_225_600;
+
+fn review(title: &str) -> ReviewInput {
+    ReviewInput {
+        title: title.to_string(),
+        description: "Such good movie, really great, very amazing".to_string(),
+        rating: 8,
+        genre: Genre::ScienceFiction,
+        release_year: 2010,
+        tags: vec!["mind-bending".to_string()],
+    }
+}
+
+// Loads the program, sets the clock and creates the reward mint and the moderation config
+// with `admin` as their admin
+fn setup() -> (LiteSVM, Keypair) {
+    load(&test_utils::program_binary("movie_review"))
+}
+
+// Same as setup, with a binary that has already been read
+fn load(binary: &[u8]) -> (LiteSVM, Keypair) {
+    let (mut svm, admin) = deploy(binary);
+    send(
+        &mut svm,
+        initialize_reward_mint(&admin.pubkey(), REWARD_AMOUNT),
+        &admin,
+    )
+    .unwrap();
+    send(&mut svm, initialize_config(&admin.pubkey()), &admin).unwrap();
+
+    (svm, admin)
+}
+
+// Deploys the program with a new wallet as its upgrade authority, which is returned, and sets
+// the clock. Nothing is initialized yet.
+fn deploy(binary: &[u8]) -> (LiteSVM, Keypair) {
+    let mut svm = LiteSVM::new();
+    let upgrade_authority = funded_wallet(&mut svm);
+    test_utils::deploy_upgradeable(
+        &mut svm,
+        movie_review::ID,
+        binary,
+        &upgrade_authority.pubkey(),
+    );
+
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = NOW;
+    svm.set_sysvar::<Clock>(&clock);
+
+    (svm, upgrade_authority)
+}
+
+// Points the review account of an update or delete at `review`, which the client would
+// otherwise derive from the title
+fn with_review(mut instruction: Instruction, review: Pubkey) -> Instruction {
+    instruction.accounts[0].pubkey = review;
+    instruction
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn add_creates_a_review_sized_to_its_content() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    let inception = review("Inception");
+
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &inception),
+        &reviewer,
+    )
+    .unwrap();
+
+    let address = review_address("Inception", &reviewer.pubkey());
+    let stored: MovieReviewData = fetch(&svm, &address);
+    assert_eq!(stored.reviewer, reviewer.pubkey());
+    assert_eq!(stored.title, "Inception");
+    assert_eq!(stored.rating, 8);
+    assert_eq!(stored.created_at, NOW);
+    assert_eq!(stored.edit_count, 0);
+    assert!(stored.status == ReviewStatus::Visible);
+
+    let account = svm.get_account(&address).unwrap();
+    assert_eq!(
+        account.data.len(),
+        MovieReviewData::space(&inception.title, &inception.description, &inception.tags)
+    );
+    assert_eq!(
+        account.lamports,
+        svm.minimum_balance_for_rent_exemption(account.data.len())
+    );
+
+    let movie: Movie = fetch(&svm, &movie_address("Inception"));
+    assert_eq!(movie.review_count, 1);
+    assert_eq!(movie.average_rating(), Some(8.0));
+
+    let profile: ReviewerProfile = fetch(&svm, &profile_address(&reviewer.pubkey()));
+    assert_eq!(profile.review_count, 1);
+
+    // The reward lands in the reviewer's associated token account
+    let token_account: token::TokenAccount =
+        fetch(&svm, &reviewer_token_account(&reviewer.pubkey()));
+    assert_eq!(token_account.amount, REWARD_AMOUNT);
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn only_the_upgrade_authority_can_create_the_reward_mint() {
+    let (mut svm, upgrade_authority) = deploy(&test_utils::program_binary("movie_review"));
+    let squatter = funded_wallet(&mut svm);
+
+    assert_error(
+        send(
+            &mut svm,
+            initialize_reward_mint(&squatter.pubkey(), REWARD_AMOUNT),
+            &squatter,
+        ),
+        MovieReviewError::NotUpgradeAuthority,
+    );
+
+    send(
+        &mut svm,
+        initialize_reward_mint(&upgrade_authority.pubkey(), REWARD_AMOUNT),
+        &upgrade_authority,
+    )
+    .unwrap();
+    let config: RewardConfig = fetch(&svm, &reward_config_address());
+    assert_eq!(config.admin, upgrade_authority.pubkey());
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn only_the_upgrade_authority_can_create_the_config() {
+    let (mut svm, upgrade_authority) = deploy(&test_utils::program_binary("movie_review"));
+    let squatter = funded_wallet(&mut svm);
+
+    assert_error(
+        send(&mut svm, initialize_config(&squatter.pubkey()), &squatter),
+        MovieReviewError::NotUpgradeAuthority,
+    );
+
+    send(
+        &mut svm,
+        initialize_config(&upgrade_authority.pubkey()),
+        &upgrade_authority,
+    )
+    .unwrap();
+    let config: Config = fetch(&svm, &config_address());
+    assert_eq!(config.admin, upgrade_authority.pubkey());
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn titles_are_normalized_before_hashing() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Inception")),
+        &reviewer,
+    )
+    .unwrap();
+
+    // A title longer than 32 bytes still derives a valid seed
+    let long_title = "The Lord of the Rings: The Return of the King";
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review(long_title)),
+        &reviewer,
+    )
+    .unwrap();
+
+    assert_eq!(
+        review_address("INCEPTION", &reviewer.pubkey()),
+        review_address("Inception", &reviewer.pubkey())
+    );
+    let update = review("INCEPTION");
+    send(
+        &mut svm,
+        update_movie_review(&reviewer.pubkey(), &update),
+        &reviewer,
+    )
+    .unwrap();
+
+    // The capitalization of the update is stored
+    let stored: MovieReviewData = fetch(&svm, &review_address("Inception", &reviewer.pubkey()));
+    assert_eq!(stored.title, "INCEPTION");
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn invalid_review_text_is_rejected() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+
+    let cases: Vec<(ReviewInput, MovieReviewError)> = vec![
+        (
+            ReviewInput {
+                rating: 0,
+                ..review("Zero")
+            },
+            MovieReviewError::InvalidRating,
+        ),
+        (
+            ReviewInput {
+                rating: 11,
+                ..review("Eleven")
+            },
+            MovieReviewError::InvalidRating,
+        ),
+        (review(""), MovieReviewError::EmptyTitle),
+        (
+            review(&"a".repeat(MAX_TITLE_LENGTH + 1)),
+            MovieReviewError::TitleTooLong,
+        ),
+        (
+            review("Bell\u{7}"),
+            MovieReviewError::TitleContainsControlCharacter,
+        ),
+        (
+            review(" Padded"),
+            MovieReviewError::TitleHasSurroundingWhitespace,
+        ),
+        (
+            ReviewInput {
+                description: "a".repeat(MAX_DESCRIPTION_LENGTH + 1),
+                ..review("Wordy")
+            },
+            MovieReviewError::DescriptionTooLong,
+        ),
+        (
+            ReviewInput {
+                description: "Tab\tseparated".to_string(),
+                ..review("Tabs")
+            },
+            MovieReviewError::DescriptionContainsControlCharacter,
+        ),
+    ];
+
+    for (invalid, error) in cases {
+        assert_error(
+            send(
+                &mut svm,
+                add_movie_review(&reviewer.pubkey(), &invalid),
+                &reviewer,
+            ),
+            error,
+        );
+    }
+
+    // Limits are counted in characters: 80 CJK characters take 240 bytes and are accepted
+    let cjk_title = "映".repeat(MAX_TITLE_LENGTH);
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review(&cjk_title)),
+        &reviewer,
+    )
+    .unwrap();
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn invalid_metadata_is_rejected() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+
+    let cases: Vec<(ReviewInput, MovieReviewError)> = vec![
+        (
+            ReviewInput {
+                release_year: 1800,
+                ..review("Too old")
+            },
+            MovieReviewError::InvalidReleaseYear,
+        ),
+        (
+            ReviewInput {
+                release_year: 2030,
+                ..review("Too new")
+            },
+            MovieReviewError::InvalidReleaseYear,
+        ),
+        (
+            ReviewInput {
+                tags: vec!["tag".to_string(); MAX_TAGS + 1],
+                ..review("Tagged")
+            },
+            MovieReviewError::TooManyTags,
+        ),
+        (
+            ReviewInput {
+                tags: vec!["a".repeat(MAX_TAG_LENGTH + 1)],
+                ..review("Long tag")
+            },
+            MovieReviewError::TagTooLong,
+        ),
+        (
+            ReviewInput {
+                tags: vec!["two words".to_string()],
+                ..review("Spaced tag")
+            },
+            MovieReviewError::InvalidTag,
+        ),
+        (
+            ReviewInput {
+                tags: vec![String::new()],
+                ..review("Empty tag")
+            },
+            MovieReviewError::InvalidTag,
+        ),
+    ];
+
+    for (invalid, error) in cases {
+        assert_error(
+            send(
+                &mut svm,
+                add_movie_review(&reviewer.pubkey(), &invalid),
+                &reviewer,
+            ),
+            error,
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn update_reallocs_and_refunds_rent_when_shrinking() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    let address = review_address("Inception", &reviewer.pubkey());
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Inception")),
+        &reviewer,
+    )
+    .unwrap();
+
+    // Growing the description makes the reviewer pay for the extra rent
+    let longer = ReviewInput {
+        description: "a".repeat(MAX_DESCRIPTION_LENGTH),
+        rating: 3,
+        ..review("Inception")
+    };
+    let reviewer_before = balance(&svm, &reviewer.pubkey());
+    let review_before = balance(&svm, &address);
+    send(
+        &mut svm,
+        update_movie_review(&reviewer.pubkey(), &longer),
+        &reviewer,
+    )
+    .unwrap();
+
+    let grown = svm.get_account(&address).unwrap();
+    let expected_space = MovieReviewData::space(&longer.title, &longer.description, &longer.tags);
+    assert_eq!(grown.data.len(), expected_space);
+    assert_eq!(
+        grown.lamports,
+        svm.minimum_balance_for_rent_exemption(expected_space)
+    );
+    assert_eq!(
+        balance(&svm, &reviewer.pubkey()),
+        reviewer_before - (grown.lamports - review_before) - SIGNATURE_FEE
+    );
+
+    // Shrinking it refunds the rent that is no longer needed
+    let shorter = ReviewInput {
+        description: "Short".to_string(),
+        ..review("Inception")
+    };
+    let reviewer_before = balance(&svm, &reviewer.pubkey());
+    send(
+        &mut svm,
+        update_movie_review(&reviewer.pubkey(), &shorter),
+        &reviewer,
+    )
+    .unwrap();
+
+    let shrunk = svm.get_account(&address).unwrap();
+    let expected_space =
+        MovieReviewData::space(&shorter.title, &shorter.description, &shorter.tags);
+    assert_eq!(shrunk.data.len(), expected_space);
+    assert_eq!(
+        shrunk.lamports,
+        svm.minimum_balance_for_rent_exemption(expected_space)
+    );
+    assert_eq!(
+        balance(&svm, &reviewer.pubkey()),
+        reviewer_before + (grown.lamports - shrunk.lamports) - SIGNATURE_FEE
+    );
+
+    let stored: MovieReviewData = fetch(&svm, &address);
+    assert_eq!(stored.description, "Short");
+    assert_eq!(stored.edit_count, 2);
+
+    // The aggregate swapped the old rating for the new one
+    let movie: Movie = fetch(&svm, &movie_address("Inception"));
+    assert_eq!(movie.review_count, 1);
+    assert_eq!(movie.rating_sum, 8);
+    assert_eq!(movie.rating_histogram[2], 0);
+    assert_eq!(movie.rating_histogram[7], 1);
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn delete_closes_the_review_and_refunds_its_rent() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    let address = review_address("Inception", &reviewer.pubkey());
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Inception")),
+        &reviewer,
+    )
+    .unwrap();
+
+    let rent = balance(&svm, &address);
+    let reviewer_before = balance(&svm, &reviewer.pubkey());
+    send(
+        &mut svm,
+        delete_movie_review(&reviewer.pubkey(), "Inception"),
+        &reviewer,
+    )
+    .unwrap();
+
+    assert!(is_closed(&svm, &address));
+    assert_eq!(
+        balance(&svm, &reviewer.pubkey()),
+        reviewer_before + rent - SIGNATURE_FEE
+    );
+
+    let movie: Movie = fetch(&svm, &movie_address("Inception"));
+    assert_eq!(movie.review_count, 0);
+    assert_eq!(movie.average_rating(), None);
+    let profile: ReviewerProfile = fetch(&svm, &profile_address(&reviewer.pubkey()));
+    assert_eq!(profile.review_count, 0);
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn a_title_is_only_rewarded_once_per_reviewer() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    let token_account = reviewer_token_account(&reviewer.pubkey());
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Inception")),
+        &reviewer,
+    )
+    .unwrap();
+
+    // Deleting the review and writing it again doesn't pay a second reward
+    for _ in 0..2 {
+        send(
+            &mut svm,
+            delete_movie_review(&reviewer.pubkey(), "Inception"),
+            &reviewer,
+        )
+        .unwrap();
+        send(
+            &mut svm,
+            add_movie_review(&reviewer.pubkey(), &review("Inception")),
+            &reviewer,
+        )
+        .unwrap();
+    }
+    let rewards: token::TokenAccount = fetch(&svm, &token_account);
+    assert_eq!(rewards.amount, REWARD_AMOUNT);
+    assert!(!is_closed(
+        &svm,
+        &reward_receipt_address("Inception", &reviewer.pubkey())
+    ));
+
+    // Neither does renaming the review, deleting it and writing one for the new title
+    send(
+        &mut svm,
+        rename_movie_review(&reviewer.pubkey(), "Inception", "Tenet"),
+        &reviewer,
+    )
+    .unwrap();
+    send(
+        &mut svm,
+        delete_movie_review(&reviewer.pubkey(), "Tenet"),
+        &reviewer,
+    )
+    .unwrap();
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Tenet")),
+        &reviewer,
+    )
+    .unwrap();
+    let rewards: token::TokenAccount = fetch(&svm, &token_account);
+    assert_eq!(rewards.amount, REWARD_AMOUNT);
+
+    // A review of another title is rewarded as usual
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Memento")),
+        &reviewer,
+    )
+    .unwrap();
+    let rewards: token::TokenAccount = fetch(&svm, &token_account);
+    assert_eq!(rewards.amount, 2 * REWARD_AMOUNT);
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn review_that_does_not_match_the_seeds_is_rejected() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    let other = funded_wallet(&mut svm);
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Inception")),
+        &reviewer,
+    )
+    .unwrap();
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Tenet")),
+        &reviewer,
+    )
+    .unwrap();
+
+    // The review of another title
+    let tenet = review_address("Tenet", &reviewer.pubkey());
+    assert_error(
+        send(
+            &mut svm,
+            with_review(
+                update_movie_review(&reviewer.pubkey(), &review("Inception")),
+                tenet,
+            ),
+            &reviewer,
+        ),
+        anchor_lang::error::ErrorCode::ConstraintSeeds,
+    );
+
+    // The review of another reviewer
+    let inception = review_address("Inception", &reviewer.pubkey());
+    assert_error(
+        send(
+            &mut svm,
+            with_review(delete_movie_review(&other.pubkey(), "Inception"), inception),
+            &other,
+        ),
+        anchor_lang::error::ErrorCode::ConstraintSeeds,
+    );
+    assert!(!is_closed(&svm, &inception));
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn comments_are_validated_and_only_deleted_by_their_author() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    let stranger = funded_wallet(&mut svm);
+    let address = review_address("Inception", &reviewer.pubkey());
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Inception")),
+        &reviewer,
+    )
+    .unwrap();
+
+    for (body, error) in [
+        (String::new(), MovieReviewError::EmptyComment),
+        (
+            "a".repeat(MAX_COMMENT_LENGTH + 1),
+            MovieReviewError::CommentTooLong,
+        ),
+        (
+            "Null\0byte".to_string(),
+            MovieReviewError::CommentContainsControlCharacter,
+        ),
+    ] {
+        assert_error(
+            send(
+                &mut svm,
+                add_comment(&reviewer.pubkey(), &address, 0, &body),
+                &reviewer,
+            ),
+            error,
+        );
+    }
+
+    send(
+        &mut svm,
+        add_comment(&reviewer.pubkey(), &address, 0, "First!"),
+        &reviewer,
+    )
+    .unwrap();
+    send(
+        &mut svm,
+        add_comment(&stranger.pubkey(), &address, 1, "Agreed"),
+        &stranger,
+    )
+    .unwrap();
+
+    let comment: Comment = fetch(&svm, &comment_address(&address, 1));
+    assert_eq!(comment.author, stranger.pubkey());
+    assert_eq!(comment.body, "Agreed");
+
+    let first = comment_address(&address, 0);
+    assert_error(
+        send(
+            &mut svm,
+            delete_comment(&stranger.pubkey(), &address, 0),
+            &stranger,
+        ),
+        MovieReviewError::NotCommentAuthor,
+    );
+
+    let rent = balance(&svm, &first);
+    let author_before = balance(&svm, &reviewer.pubkey());
+    send(
+        &mut svm,
+        delete_comment(&reviewer.pubkey(), &address, 0),
+        &reviewer,
+    )
+    .unwrap();
+    assert!(is_closed(&svm, &first));
+    assert_eq!(
+        balance(&svm, &reviewer.pubkey()),
+        author_before + rent - SIGNATURE_FEE
+    );
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn reviews_with_likes_or_comments_cannot_be_renamed() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    let voter = funded_wallet(&mut svm);
+    let address = review_address("Inception", &reviewer.pubkey());
+    let rename = rename_movie_review(&reviewer.pubkey(), "Inception", "Tenet");
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Inception")),
+        &reviewer,
+    )
+    .unwrap();
+
+    send(
+        &mut svm,
+        like_review(&voter.pubkey(), &address, &reviewer.pubkey()),
+        &voter,
+    )
+    .unwrap();
+    assert_error(
+        send(&mut svm, rename.clone(), &reviewer),
+        MovieReviewError::ReviewHasLikes,
+    );
+    send(
+        &mut svm,
+        unlike_review(&voter.pubkey(), &address, &reviewer.pubkey()),
+        &voter,
+    )
+    .unwrap();
+
+    send(
+        &mut svm,
+        add_comment(&voter.pubkey(), &address, 0, "First!"),
+        &voter,
+    )
+    .unwrap();
+    assert_error(
+        send(&mut svm, rename.clone(), &reviewer),
+        MovieReviewError::ReviewHasComments,
+    );
+    send(
+        &mut svm,
+        delete_comment(&voter.pubkey(), &address, 0),
+        &voter,
+    )
+    .unwrap();
+
+    send(&mut svm, rename, &reviewer).unwrap();
+    assert!(is_closed(&svm, &address));
+    let renamed: MovieReviewData = fetch(&svm, &review_address("Tenet", &reviewer.pubkey()));
+    assert_eq!(renamed.title, "Tenet");
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn comments_of_a_deleted_review_do_not_block_renaming_its_successor() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    let commenter = funded_wallet(&mut svm);
+    let address = review_address("Inception", &reviewer.pubkey());
+    let add = add_movie_review(&reviewer.pubkey(), &review("Inception"));
+    let rename = rename_movie_review(&reviewer.pubkey(), "Inception", "Tenet");
+    send(&mut svm, add.clone(), &reviewer).unwrap();
+    send(
+        &mut svm,
+        add_comment(&commenter.pubkey(), &address, 0, "First!"),
+        &commenter,
+    )
+    .unwrap();
+    send(
+        &mut svm,
+        delete_movie_review(&reviewer.pubkey(), "Inception"),
+        &reviewer,
+    )
+    .unwrap();
+
+    // The comment left behind belongs to the deleted review, not the new one
+    send(&mut svm, add, &reviewer).unwrap();
+    send(
+        &mut svm,
+        add_comment(&commenter.pubkey(), &address, 1, "Second!"),
+        &commenter,
+    )
+    .unwrap();
+    send(
+        &mut svm,
+        delete_comment(&commenter.pubkey(), &address, 0),
+        &commenter,
+    )
+    .unwrap();
+    let counter: CommentCounter = fetch(&svm, &comment_counter_address(&address));
+    assert_eq!(counter.count, 2);
+    assert_eq!(counter.open, 1);
+    assert_error(
+        send(&mut svm, rename.clone(), &reviewer),
+        MovieReviewError::ReviewHasComments,
+    );
+
+    send(
+        &mut svm,
+        delete_comment(&commenter.pubkey(), &address, 1),
+        &commenter,
+    )
+    .unwrap();
+    send(&mut svm, rename, &reviewer).unwrap();
+    assert!(is_closed(&svm, &address));
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn moderators_hide_and_unhide_reviews() {
+    let (mut svm, admin) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    let moderator = funded_wallet(&mut svm);
+    let address = review_address("Inception", &reviewer.pubkey());
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Inception")),
+        &reviewer,
+    )
+    .unwrap();
+
+    assert_error(
+        send(
+            &mut svm,
+            hide_review(&moderator.pubkey(), &address),
+            &moderator,
+        ),
+        MovieReviewError::NotModerator,
+    );
+
+    send(
+        &mut svm,
+        add_moderator(&admin.pubkey(), &moderator.pubkey()),
+        &admin,
+    )
+    .unwrap();
+    assert_error(
+        send(
+            &mut svm,
+            add_moderator(&admin.pubkey(), &moderator.pubkey()),
+            &admin,
+        ),
+        MovieReviewError::AlreadyModerator,
+    );
+    assert_error(
+        send(
+            &mut svm,
+            unhide_review(&moderator.pubkey(), &address),
+            &moderator,
+        ),
+        MovieReviewError::ReviewNotHidden,
+    );
+
+    send(
+        &mut svm,
+        hide_review(&moderator.pubkey(), &address),
+        &moderator,
+    )
+    .unwrap();
+    let stored: MovieReviewData = fetch(&svm, &address);
+    assert!(stored.is_hidden());
+    assert_error(
+        send(
+            &mut svm,
+            hide_review(&moderator.pubkey(), &address),
+            &moderator,
+        ),
+        MovieReviewError::ReviewAlreadyHidden,
+    );
+
+    send(
+        &mut svm,
+        unhide_review(&moderator.pubkey(), &address),
+        &moderator,
+    )
+    .unwrap();
+    let stored: MovieReviewData = fetch(&svm, &address);
+    assert!(!stored.is_hidden());
+
+    // Removed moderators lose their rights
+    send(
+        &mut svm,
+        remove_moderator(&admin.pubkey(), &moderator.pubkey()),
+        &admin,
+    )
+    .unwrap();
+    assert_error(
+        send(
+            &mut svm,
+            remove_moderator(&admin.pubkey(), &moderator.pubkey()),
+            &admin,
+        ),
+        MovieReviewError::NotModerator,
+    );
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn each_wallet_reports_a_review_once() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    let reporter = funded_wallet(&mut svm);
+    let other_reporter = funded_wallet(&mut svm);
+    let address = review_address("Inception", &reviewer.pubkey());
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Inception")),
+        &reviewer,
+    )
+    .unwrap();
+
+    send(
+        &mut svm,
+        report_review(&reporter.pubkey(), &address),
+        &reporter,
+    )
+    .unwrap();
+    // The report marker already exists, so the system program refuses to create it again
+    assert!(send(
+        &mut svm,
+        report_review(&reporter.pubkey(), &address),
+        &reporter
+    )
+    .is_err());
+    send(
+        &mut svm,
+        report_review(&other_reporter.pubkey(), &address),
+        &other_reporter,
+    )
+    .unwrap();
+
+    let stored: MovieReviewData = fetch(&svm, &address);
+    assert_eq!(stored.report_count, 2);
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn moderator_list_is_bounded() {
+    let (mut svm, admin) = setup();
+
+    for _ in 0..MAX_MODERATORS {
+        send(
+            &mut svm,
+            add_moderator(&admin.pubkey(), &Pubkey::new_unique()),
+            &admin,
+        )
+        .unwrap();
+    }
+
+    assert_error(
+        send(
+            &mut svm,
+            add_moderator(&admin.pubkey(), &Pubkey::new_unique()),
+            &admin,
+        ),
+        MovieReviewError::TooManyModerators,
+    );
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn likes_are_counted_once_and_unliking_refunds_the_marker() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    let voter = funded_wallet(&mut svm);
+    let address = review_address("Inception", &reviewer.pubkey());
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Inception")),
+        &reviewer,
+    )
+    .unwrap();
+
+    assert_error(
+        send(
+            &mut svm,
+            like_review(&reviewer.pubkey(), &address, &reviewer.pubkey()),
+            &reviewer,
+        ),
+        MovieReviewError::CannotLikeOwnReview,
+    );
+
+    let like = like_review(&voter.pubkey(), &address, &reviewer.pubkey());
+    send(&mut svm, like.clone(), &voter).unwrap();
+    assert!(send(&mut svm, like, &voter).is_err());
+
+    let stored: MovieReviewData = fetch(&svm, &address);
+    assert_eq!(stored.like_count, 1);
+    let marker: Like = fetch(&svm, &like_address(&address, &voter.pubkey()));
+    assert_eq!(marker.voter, voter.pubkey());
+    let profile: ReviewerProfile = fetch(&svm, &profile_address(&reviewer.pubkey()));
+    assert_eq!(profile.likes_received, 1);
+    assert_eq!(
+        profile.reputation,
+        REPUTATION_PER_REVIEW + REPUTATION_PER_LIKE
+    );
+
+    let marker_address = like_address(&address, &voter.pubkey());
+    let rent = balance(&svm, &marker_address);
+    let voter_before = balance(&svm, &voter.pubkey());
+    send(
+        &mut svm,
+        unlike_review(&voter.pubkey(), &address, &reviewer.pubkey()),
+        &voter,
+    )
+    .unwrap();
+
+    assert!(is_closed(&svm, &marker_address));
+    assert_eq!(
+        balance(&svm, &voter.pubkey()),
+        voter_before + rent - SIGNATURE_FEE
+    );
+    let stored: MovieReviewData = fetch(&svm, &address);
+    assert_eq!(stored.like_count, 0);
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn likes_of_a_deleted_review_can_still_be_removed() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    let voter = funded_wallet(&mut svm);
+    let address = review_address("Inception", &reviewer.pubkey());
+    let marker_address = like_address(&address, &voter.pubkey());
+    let like = like_review(&voter.pubkey(), &address, &reviewer.pubkey());
+    let unlike = unlike_review(&voter.pubkey(), &address, &reviewer.pubkey());
+    let add = add_movie_review(&reviewer.pubkey(), &review("Inception"));
+    let delete = delete_movie_review(&reviewer.pubkey(), "Inception");
+
+    send(&mut svm, add.clone(), &reviewer).unwrap();
+    send(&mut svm, like.clone(), &voter).unwrap();
+    send(&mut svm, delete.clone(), &reviewer).unwrap();
+    send(&mut svm, unlike.clone(), &voter).unwrap();
+
+    assert!(is_closed(&svm, &marker_address));
+    let profile: ReviewerProfile = fetch(&svm, &profile_address(&reviewer.pubkey()));
+    assert_eq!(profile.likes_received, 0);
+
+    // A like of the deleted review must not be taken off a new review at the same address,
+    // even one written in the same second
+    send(&mut svm, add.clone(), &reviewer).unwrap();
+    send(&mut svm, like, &voter).unwrap();
+    send(&mut svm, delete, &reviewer).unwrap();
+    send(&mut svm, add, &reviewer).unwrap();
+    send(&mut svm, unlike, &voter).unwrap();
+
+    assert!(is_closed(&svm, &marker_address));
+    let stored: MovieReviewData = fetch(&svm, &address);
+    assert_eq!(stored.like_count, 0);
+    assert_eq!(stored.nonce, 2);
+    let profile: ReviewerProfile = fetch(&svm, &profile_address(&reviewer.pubkey()));
+    assert_eq!(profile.likes_received, 0);
+    assert_eq!(profile.reputation, REPUTATION_PER_REVIEW);
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn unlike_of_a_review_without_likes_is_rejected() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+    let voter = funded_wallet(&mut svm);
+    let address = review_address("Inception", &reviewer.pubkey());
+    send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), &review("Inception")),
+        &reviewer,
+    )
+    .unwrap();
+    send(
+        &mut svm,
+        like_review(&voter.pubkey(), &address, &reviewer.pubkey()),
+        &voter,
+    )
+    .unwrap();
+
+    // No instruction can get the counts out of sync with the like markers, so the review is
+    // rewritten with no likes while the voter's marker still exists
+    let mut stored: MovieReviewData = fetch(&svm, &address);
+    stored.like_count = 0;
+    let mut account = svm.get_account(&address).unwrap();
+    let mut data = Vec::new();
+    stored.try_serialize(&mut data).unwrap();
+    account.data = data;
+    svm.set_account(address, account).unwrap();
+
+    assert_error(
+        send(
+            &mut svm,
+            unlike_review(&voter.pubkey(), &address, &reviewer.pubkey()),
+            &voter,
+        ),
+        MovieReviewError::LikeCountUnderflow,
+    );
+    assert!(!is_closed(&svm, &like_address(&address, &voter.pubkey())));
+}
+
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn invalid_display_names_are_rejected() {
+    let (mut svm, _) = setup();
+    let reviewer = funded_wallet(&mut svm);
+
+    assert_error(
+        send(
+            &mut svm,
+            update_profile(&reviewer.pubkey(), &"a".repeat(MAX_DISPLAY_NAME_LENGTH + 1)),
+            &reviewer,
+        ),
+        MovieReviewError::DisplayNameTooLong,
+    );
+    assert_error(
+        send(
+            &mut svm,
+            update_profile(&reviewer.pubkey(), "Critic\n"),
+            &reviewer,
+        ),
+        MovieReviewError::InvalidDisplayName,
+    );
+
+    send(
+        &mut svm,
+        update_profile(&reviewer.pubkey(), "Critic"),
+        &reviewer,
+    )
+    .unwrap();
+    let profile: ReviewerProfile = fetch(&svm, &profile_address(&reviewer.pubkey()));
+    assert_eq!(profile.display_name, "Critic");
+}
+
+// Arbitrary Unicode, control characters and whitespace included. Whatever the title, an
+// accepted review must respect the character limit and fit its account without spare bytes,
+// and an oversize title must be rejected for its length before anything else is checked.
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn length_check_never_lets_an_oversize_title_through() {
+    // Read once instead of in each case
+    let binary = test_utils::program_binary("movie_review");
+    let title =
+        prop::collection::vec(any::<char>(), 0..MAX_TITLE_LENGTH + 10).prop_map(String::from_iter);
+    // Every case starts a fresh LiteSVM, so keep the number of cases modest
+    let mut runner = TestRunner::new(ProptestConfig::with_cases(64));
+
+    let result = runner.run(&title, |title| {
+        let input = ReviewInput {
+            title,
+            ..review("")
+        };
+        let error = add_arbitrary_review(&binary, &input)?;
+
+        if input.title.chars().count() > MAX_TITLE_LENGTH {
+            prop_assert_eq!(error, Some(program_error(MovieReviewError::TitleTooLong)));
+        }
+        Ok(())
+    });
+
+    // The error names the smallest input that proptest found to fail
+    result.unwrap();
+}
+
+// Same for the description, under a valid title. Its characters are at most 2 bytes long so
+// that an oversize description still fits in a transaction, which caps the bytes, not the
+// characters.
+#[test]
+#[ignore = "needs target/deploy/movie_review.so, see the top of this file"]
+fn length_check_never_lets_an_oversize_description_through() {
+    let binary = test_utils::program_binary("movie_review");
+    let description = prop::collection::vec(
+        prop::char::range('\0', '\u{7FF}'),
+        0..MAX_DESCRIPTION_LENGTH + 10,
+    )
+    .prop_map(String::from_iter);
+    let mut runner = TestRunner::new(ProptestConfig::with_cases(64));
+
+    let result = runner.run(&description, |description| {
+        let input = ReviewInput {
+            description,
+            ..review("Inception")
+        };
+        let error = add_arbitrary_review(&binary, &input)?;
+
+        if input.description.chars().count() > MAX_DESCRIPTION_LENGTH {
+            prop_assert_eq!(
+                error,
+                Some(program_error(MovieReviewError::DescriptionTooLong))
+            );
+        }
+        Ok(())
+    });
+
+    result.unwrap();
+}
+
+// Adds the review to a freshly loaded program and returns the transaction error, if any. An
+// accepted review must be stored as is in an account of exactly its serialized size.
+fn add_arbitrary_review(
+    binary: &[u8],
+    input: &ReviewInput,
+) -> Result<Option<TransactionError>, TestCaseError> {
+    let (mut svm, _) = load(binary);
+    let reviewer = funded_wallet(&mut svm);
+
+    if let Err(failure) = send(
+        &mut svm,
+        add_movie_review(&reviewer.pubkey(), input),
+        &reviewer,
+    ) {
+        return Ok(Some(failure.err));
+    }
+
+    let address = review_address(&input.title, &reviewer.pubkey());
+    let stored: MovieReviewData = fetch(&svm, &address);
+    prop_assert!(input.title.chars().count() <= MAX_TITLE_LENGTH);
+    prop_assert!(input.description.chars().count() <= MAX_DESCRIPTION_LENGTH);
+    prop_assert_eq!(&stored.title, &input.title);
+    prop_assert_eq!(&stored.description, &input.description);
+
+    // The account holds exactly the serialized review, and never more than the maximum size
+    // reserved by INIT_SPACE
+    let mut serialized = Vec::new();
+    stored.try_serialize(&mut serialized).unwrap();
+    let account = svm.get_account(&address).unwrap();
+    prop_assert_eq!(account.data.len(), serialized.len());
+    prop_assert!(account.data.len() <= 8 + MovieReviewData::INIT_SPACE);
+    Ok(None)
+}
@@ -0,0 +1,147 @@
+// Helpers shared by the LiteSVM integration tests of the programs in this workspace. LiteSVM
+// runs programs in-process, so the tests need no validator and no network, only the program
+// binaries that `anchor build` writes to target/deploy. Those tests are marked #[ignore] so
+// that `cargo test` doesn't count them as passed without a binary, run them with
+// `cargo test -p <program> -- --include-ignored` after `anchor build`.
+use anchor_lang::AccountDeserialize;
+use litesvm::{types::TransactionResult, LiteSVM};
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use solana_sdk_ids::bpf_loader_upgradeable;
+use std::path::Path;
+
+// LiteSVM charges the default fee of 5000 lamports per signature. Every transaction sent by
+// `send` has a single signer, so this is also its total fee.
+pub const SIGNATURE_FEE: u64 = 5000;
+
+// Reads the binary of a program built by `anchor build`, e.g. `program_binary("movie_review")`.
+// The tests that need it are ignored by default, so this only runs when they are asked for
+// and a missing binary fails them instead of letting them pass without running anything.
+pub fn program_binary(name: &str) -> Vec<u8> {
+    // This crate sits at the root of the workspace, next to the target directory
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("the crate is inside the workspace")
+        .join("target/deploy")
+        .join(format!("{name}.so"));
+
+    std::fs::read(&path).unwrap_or_else(|err| {
+        panic!(
+            "can't read {} ({err}), run `anchor build` before the program tests",
+            path.display()
+        )
+    })
+}
+
+// Deploys a program with the upgradeable loader, the way `solana program deploy` does, so
+// that it has a ProgramData account recording `upgrade_authority`. LiteSVM::add_program uses
+// a loader without upgrade authorities, which is enough for programs that never read it.
+pub fn deploy_upgradeable(
+    svm: &mut LiteSVM,
+    program_id: Pubkey,
+    binary: &[u8],
+    upgrade_authority: &Pubkey,
+) {
+    let (programdata_address, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::ID);
+
+    // The ProgramData account holds its state, padded to a fixed size, followed by the ELF
+    let mut programdata = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+        slot: 0,
+        upgrade_authority_address: Some(*upgrade_authority),
+    })
+    .unwrap();
+    programdata.resize(UpgradeableLoaderState::size_of_programdata_metadata(), 0);
+    programdata.extend_from_slice(binary);
+    set_loader_account(svm, programdata_address, programdata, false);
+
+    // The program account only points to its ProgramData. LiteSVM loads the program when
+    // the account is set, so the ProgramData has to be set first.
+    let program = bincode::serialize(&UpgradeableLoaderState::Program {
+        programdata_address,
+    })
+    .unwrap();
+    set_loader_account(svm, program_id, program, true);
+}
+
+fn set_loader_account(svm: &mut LiteSVM, address: Pubkey, data: Vec<u8>, executable: bool) {
+    svm.set_account(
+        address,
+        Account {
+            lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: bpf_loader_upgradeable::ID,
+            executable,
+            rent_epoch: 0,
+        },
+    )
+    .expect("the program should load");
+}
+
+pub fn funded_wallet(svm: &mut LiteSVM) -> Keypair {
+    let wallet = Keypair::new();
+    svm.airdrop(&wallet.pubkey(), 10 * LAMPORTS_PER_SOL)
+        .expect("airdrop should succeed");
+    wallet
+}
+
+// Sends a transaction with a single instruction, signed and paid for by `signer`.
+// TransactionResult is LiteSVM's own type, so its large error variant can't be boxed here.
+#[allow(clippy::result_large_err)]
+pub fn send(svm: &mut LiteSVM, instruction: Instruction, signer: &Keypair) -> TransactionResult {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&signer.pubkey()),
+        &[signer],
+        svm.latest_blockhash(),
+    );
+    let result = svm.send_transaction(transaction);
+    // The runtime rejects a transaction it has already seen in the same block. A new
+    // blockhash lets tests send the exact same instruction twice, e.g. to check that a
+    // second like fails.
+    svm.expire_blockhash();
+    result
+}
+
+// Reads an Anchor account and decodes it, checking its discriminator
+pub fn fetch<T: AccountDeserialize>(svm: &LiteSVM, address: &Pubkey) -> T {
+    let account = svm.get_account(address).expect("account should exist");
+    T::try_deserialize(&mut account.data.as_slice()).expect("account should decode")
+}
+
+pub fn balance(svm: &LiteSVM, address: &Pubkey) -> u64 {
+    svm.get_balance(address).unwrap_or(0)
+}
+
+// Closed accounts are removed from LiteSVM once their lamports reach zero
+pub fn is_closed(svm: &LiteSVM, address: &Pubkey) -> bool {
+    svm.get_account(address)
+        .is_none_or(|account| account.lamports == 0)
+}
+
+// Anchor reports its own constraint errors and the error codes of programs as custom
+// instruction errors. Every transaction sent by `send` has a single instruction, so the
+// failing instruction is always the first.
+#[allow(clippy::result_large_err)]
+pub fn assert_error(result: TransactionResult, code: impl Into<u32>) {
+    let failure = result.expect_err("transaction should fail");
+    assert_eq!(
+        failure.err,
+        program_error(code),
+        "logs: {:#?}",
+        failure.meta.logs
+    );
+}
+
+// The error of a transaction sent by `send` whose instruction failed with `code`, for tests
+// that can't panic on a mismatch, like proptest properties
+pub fn program_error(code: impl Into<u32>) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.into()))
+}
@@ -30,7 +30,7 @@ describe("King of the Hill Tests", () => {
   const program = anchor.workspace.KingOfTheHill as Program<KingOfTheHill>;
 
   let initialKing, newKing;
-  let gameStatePDA, prizePoolPDA;
+  let gameStatePDA, prizePoolPDA, bidHistoryPDA;
 
   // Utility function for airdrops
   async function fundWallet(account, amount) {
@@ -70,6 +70,12 @@ describe("King of the Hill Tests", () => {
       [Buffer.from("prize_pool")],
       program.programId
     );
+
+    // The bid history of a game is seeded with the address of its game state
+    [bidHistoryPDA] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("bid_history"), gameStatePDA.toBuffer()],
+      program.programId
+    );
   });
 
   // it() represents individual test cases. The first argument is the test description,
@@ -82,13 +88,23 @@ describe("King of the Hill Tests", () => {
     // anchor.BN is Anchor's BigNumber type for handling large integers safely.
     let initialPrize = new anchor.BN(1 * anchor.web3.LAMPORTS_PER_SOL);
 
+    // Player stats are seeded with the generation of the game. It is read from the game
+    // counter, which doesn't exist before the first game, so Anchor can't derive the
+    // address on its own. This is the first game, so its generation is 0.
+    const generation = new anchor.BN(0).toArrayLike(Buffer, "le", 8);
+    const [initialKingStatsPDA] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("player_stats"), gameStatePDA.toBuffer(), generation, initialKing.publicKey.toBuffer()],
+      program.programId
+    );
+
     // Calls the initialize instruction from the Rust program
     const tx = await program.methods
       .initialize(initialPrize)
-      .accounts({
+      .accountsPartial({
         gameState: gameStatePDA,
         initialKing: initialKing.publicKey,
         prizePool: prizePoolPDA,
+        initialKingStats: initialKingStatsPDA,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([initialKing]) // Initial king pays for account creation and initial prize
@@ -119,6 +135,7 @@ describe("King of the Hill Tests", () => {
           king: initialKing.publicKey, // Correct usage of current king
           payer: newKing.publicKey, // New king who pays and becomes the king
           prizePool: prizePoolPDA,
+          referrerBalance: null, // No referrer for this bid
           systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([newKing]) // Signing by newKing
@@ -136,5 +153,66 @@ describe("King of the Hill Tests", () => {
     // Assertions to confirm the state has updated as expected.
     assert.equal(updatedGameState.king.toBase58(), newKing.publicKey.toBase58(), "King should be updated to newKing.");
     assert.equal(updatedGameState.prize.toString(), newPrize.toString(), "Prize should be updated to newPrize.");
+
+    // Both the initial prize and the new bid should be in the bid history
+    const bidHistory = await program.account.bidHistory.fetch(bidHistoryPDA);
+    assert.equal(bidHistory.totalBids.toNumber(), 2, "Both bids should be recorded.");
+    assert.equal(bidHistory.bids[1].king.toBase58(), newKing.publicKey.toBase58());
+    assert.equal(bidHistory.bids[1].amount.toString(), newPrize.toString());
+  })
+
+  it("Rejects a bid from the current king", async () => {
+    // newKing holds the crown, and the default rules forbid outbidding yourself
+    try {
+      await program.methods.becomeKing(new anchor.BN(3 * anchor.web3.LAMPORTS_PER_SOL))
+        .accounts({
+          gameState: gameStatePDA,
+          king: newKing.publicKey,
+          payer: newKing.publicKey,
+          prizePool: prizePoolPDA,
+          referrerBalance: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([newKing])
+        .rpc();
+      assert.fail("The king should not be able to outbid themselves");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "KingCannotBid");
+    }
+  })
+
+  it("Rejects bids while the game is paused", async () => {
+    // The initial king created the game, so they are its admin
+    await program.methods
+      .pause()
+      .accounts({ gameState: gameStatePDA, admin: initialKing.publicKey })
+      .signers([initialKing])
+      .rpc();
+
+    try {
+      await program.methods.becomeKing(new anchor.BN(3 * anchor.web3.LAMPORTS_PER_SOL))
+        .accounts({
+          gameState: gameStatePDA,
+          king: newKing.publicKey,
+          payer: initialKing.publicKey,
+          prizePool: prizePoolPDA,
+          referrerBalance: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([initialKing])
+        .rpc();
+      assert.fail("Bid should have been rejected while the game is paused");
+    } catch (error) {
+      assert.equal(error.error.errorCode.code, "GamePaused");
+    }
+
+    await program.methods
+      .unpause()
+      .accounts({ gameState: gameStatePDA, admin: initialKing.publicKey })
+      .signers([initialKing])
+      .rpc();
+
+    const gameState = await program.account.gameState.fetch(gameStatePDA);
+    assert.ok("active" in gameState.status, "Game should be active again.");
   })
 });
\ No newline at end of file
@@ -1,6 +1,7 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { expect } from "chai";
+import { createHash } from "crypto";
 import { MovieReviewProgram } from "../../target/types/movie_review_program"
 
 describe("movie-review-program", () => {
@@ -14,16 +15,80 @@ describe("movie-review-program", () => {
     title: "Test movie",
     description: "Such good movie, really great, very amazing",
     rating: 10,
+    genre: { scienceFiction: {} },
+    releaseYear: 2010,
+    tags: ["mind-bending", "heist"],
   }
   
+  // Mirrors `title_seed` in the program: lowercase, then SHA-256
+  const titleSeed = (title: string) =>
+    createHash("sha256").update(title.toLowerCase()).digest();
+
   const [moviePda] = anchor.web3.PublicKey.findProgramAddressSync(
-    [Buffer.from(movie.title), provider.wallet.publicKey.toBuffer()],
+    [titleSeed(movie.title), provider.wallet.publicKey.toBuffer()],
+    program.programId
+  );
+
+  const [aggregatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("movie"), titleSeed(movie.title)],
+    program.programId
+  );
+
+  // Discriminator, reviewer, genre, release year, rating, two timestamps, the edit count,
+  // the status, the report count and the like count, followed by the title, description and
+  // tags with their length prefixes
+  const reviewSpace = (title: string, description: string, tags: string[]) =>
+    8 + 32 + 1 + 2 + 1 + 8 + 8 + 4 + 1 + 4 + 8 +
+    4 + Buffer.byteLength(title) +
+    4 + Buffer.byteLength(description) +
+    4 + tags.reduce((total, tag) => total + 4 + Buffer.byteLength(tag), 0);
+
+  // Reviews that clients should show, moderators can hide reviews from this list
+  const visibleReviews = async () =>
+    (await program.account.movieReviewData.all()).filter(
+      (review) => !("hidden" in review.account.status)
+    );
+
+  const TOKEN_PROGRAM_ID = new anchor.web3.PublicKey(
+    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+  );
+  const ASSOCIATED_TOKEN_PROGRAM_ID = new anchor.web3.PublicKey(
+    "ATokenGPvbdGVxr1b2hvZbsiqW5xHRTJ2wxgKb6WmN6w"
+  );
+  const rewardAmount = new anchor.BN(5_000_000);
+
+  const [rewardMintPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("reward_mint")],
     program.programId
   );
+  const [reviewerTokenAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+    [
+      provider.wallet.publicKey.toBuffer(),
+      TOKEN_PROGRAM_ID.toBuffer(),
+      rewardMintPda.toBuffer(),
+    ],
+    ASSOCIATED_TOKEN_PROGRAM_ID
+  );
+
+  before(async () => {
+    // Reviews can only be added once there is a reward mint to pay reviewers from
+    await program.methods
+      .initializeRewardMint(rewardAmount)
+      .accounts({ tokenProgram: TOKEN_PROGRAM_ID })
+      .rpc();
+  });
   
   it("Movie review can be added", async () => {
     const tx = await program.methods
-      .addMovieReview(movie.title, movie.description, movie.rating)
+      .addMovieReview(
+        movie.title,
+        movie.description,
+        movie.rating,
+        movie.genre,
+        movie.releaseYear,
+        movie.tags
+      )
+      .accounts({ tokenProgram: TOKEN_PROGRAM_ID })
       .rpc();
       
     const account = await program.account.movieReviewData.fetch(moviePda);
@@ -32,11 +97,23 @@ describe("movie-review-program", () => {
     expect(account.title).to.equal(movie.title);
     expect(account.description).to.equal(movie.description);
     expect(account.rating).to.equal(movie.rating);
+    expect(account.editCount).to.equal(0);
+    expect(account.updatedAt.eq(account.createdAt)).to.be.true;
+
+    const info = await provider.connection.getAccountInfo(moviePda);
+    expect(info.data.length).to.equal(reviewSpace(movie.title, movie.description, movie.tags));
     
     // For PublicKey comparison, use .equals() method or convert to string
     expect(account.reviewer.equals(provider.wallet.publicKey)).to.be.true;
     // OR
     // expect(account.reviewer.toString()).to.equal(provider.wallet.publicKey.toString());
+
+    const aggregate = await program.account.movie.fetch(aggregatePda);
+    expect(aggregate.reviewCount.toNumber()).to.equal(1);
+    expect(aggregate.ratingSum.toNumber()).to.equal(movie.rating);
+
+    const reward = await provider.connection.getTokenAccountBalance(reviewerTokenAccount);
+    expect(reward.value.amount).to.equal(rewardAmount.toString());
   });
   
   it("Movie review can be updated", async () => {
@@ -44,7 +121,14 @@ describe("movie-review-program", () => {
     const newRating = 4;
     
     const tx = await program.methods
-      .updateMovieReview(movie.title, newDescription, newRating)
+      .updateMovieReview(
+        movie.title,
+        newDescription,
+        newRating,
+        movie.genre,
+        movie.releaseYear,
+        movie.tags
+      )
       .rpc();
       
     const account = await program.account.movieReviewData.fetch(moviePda);
@@ -53,6 +137,23 @@ describe("movie-review-program", () => {
     expect(account.description).to.equal(newDescription);
     expect(account.rating).to.equal(newRating);
     expect(account.reviewer.equals(provider.wallet.publicKey)).to.be.true;
+
+    expect(account.editCount).to.equal(1);
+    expect(account.updatedAt.gte(account.createdAt)).to.be.true;
+
+    // The shorter description shrinks the account and its rent is refunded
+    const info = await provider.connection.getAccountInfo(moviePda);
+    expect(info.data.length).to.equal(reviewSpace(movie.title, newDescription, movie.tags));
+    expect(info.lamports).to.equal(
+      await provider.connection.getMinimumBalanceForRentExemption(info.data.length)
+    );
+
+    // The old rating is swapped out of the movie's aggregate
+    const aggregate = await program.account.movie.fetch(aggregatePda);
+    expect(aggregate.reviewCount.toNumber()).to.equal(1);
+    expect(aggregate.ratingSum.toNumber()).to.equal(newRating);
+    expect(aggregate.ratingHistogram[movie.rating - 1].toNumber()).to.equal(0);
+    expect(aggregate.ratingHistogram[newRating - 1].toNumber()).to.equal(1);
   });
   
   it("Movie review can be deleted", async () => {
@@ -65,5 +166,516 @@ describe("movie-review-program", () => {
     } catch (error) {
       expect(error.message).to.include("Account does not exist");
     }
+
+    const aggregate = await program.account.movie.fetch(aggregatePda);
+    expect(aggregate.reviewCount.toNumber()).to.equal(0);
+    expect(aggregate.ratingSum.toNumber()).to.equal(0);
+  });
+
+  it("Movie review with a title longer than 32 bytes can be added", async () => {
+    const longTitle = "The Lord of the Rings: The Return of the King";
+
+    await program.methods
+      .addMovieReview(
+        longTitle,
+        movie.description,
+        movie.rating,
+        movie.genre,
+        movie.releaseYear,
+        movie.tags
+      )
+      .accounts({ tokenProgram: TOKEN_PROGRAM_ID })
+      .rpc();
+
+    const [longTitlePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [titleSeed(longTitle), provider.wallet.publicKey.toBuffer()],
+      program.programId
+    );
+    const account = await program.account.movieReviewData.fetch(longTitlePda);
+    expect(account.title).to.equal(longTitle);
+
+    // Titles are normalized before hashing, so a differently-cased title addresses the same review
+    await program.methods.deleteMovieReview(longTitle.toUpperCase()).rpc();
+  });
+
+  it("Title length is counted in characters, not bytes", async () => {
+    // 50 characters, 150 bytes of UTF-8
+    const cjkTitle = "映画".repeat(25);
+
+    await program.methods
+      .addMovieReview(
+        cjkTitle,
+        movie.description,
+        movie.rating,
+        movie.genre,
+        movie.releaseYear,
+        movie.tags
+      )
+      .accounts({ tokenProgram: TOKEN_PROGRAM_ID })
+      .rpc();
+
+    const [cjkPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [titleSeed(cjkTitle), provider.wallet.publicKey.toBuffer()],
+      program.programId
+    );
+    const account = await program.account.movieReviewData.fetch(cjkPda);
+    expect(account.title).to.equal(cjkTitle);
+
+    await program.methods.deleteMovieReview(cjkTitle).rpc();
+  });
+
+  it("Reviews can be filtered by genre", async () => {
+    // The genre is the byte right after the discriminator and the reviewer
+    const genreOffset = 8 + 32;
+    const scienceFictionIndex = 8;
+
+    await program.methods
+      .addMovieReview(
+        movie.title,
+        movie.description,
+        movie.rating,
+        movie.genre,
+        movie.releaseYear,
+        movie.tags
+      )
+      .accounts({ tokenProgram: TOKEN_PROGRAM_ID })
+      .rpc();
+
+    const reviews = await program.account.movieReviewData.all([
+      {
+        memcmp: {
+          offset: genreOffset,
+          bytes: anchor.utils.bytes.bs58.encode([scienceFictionIndex]),
+        },
+      },
+    ]);
+    expect(reviews.some((review) => review.publicKey.equals(moviePda))).to.be.true;
+    reviews.forEach((review) =>
+      expect(review.account.genre).to.deep.equal({ scienceFiction: {} })
+    );
+
+    await program.methods.deleteMovieReview(movie.title).rpc();
+  });
+
+  it("Release years before the first film are rejected", async () => {
+    try {
+      await program.methods
+        .addMovieReview(
+          "Ancient movie",
+          movie.description,
+          movie.rating,
+          movie.genre,
+          1800,
+          movie.tags
+        )
+        .accounts({ tokenProgram: TOKEN_PROGRAM_ID })
+        .rpc();
+      expect.fail("Release year should have been rejected");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("InvalidReleaseYear");
+    }
+  });
+
+  it("Titles with surrounding whitespace are rejected", async () => {
+    try {
+      await program.methods
+        .addMovieReview(
+          " Padded movie ",
+          movie.description,
+          movie.rating,
+          movie.genre,
+          movie.releaseYear,
+          movie.tags
+        )
+        .accounts({ tokenProgram: TOKEN_PROGRAM_ID })
+        .rpc();
+      expect.fail("Padded title should have been rejected");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("TitleHasSurroundingWhitespace");
+    }
+  });
+
+  describe("comments", () => {
+    const reviewTitle = "Commented movie";
+    const [reviewPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [titleSeed(reviewTitle), provider.wallet.publicKey.toBuffer()],
+      program.programId
+    );
+    const [counterPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("comment_counter"), reviewPda.toBuffer()],
+      program.programId
+    );
+    const commentPda = (index: number) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("comment"),
+          reviewPda.toBuffer(),
+          new anchor.BN(index).toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+    before(async () => {
+      await program.methods
+        .addMovieReview(
+          reviewTitle,
+          movie.description,
+          movie.rating,
+          movie.genre,
+          movie.releaseYear,
+          movie.tags
+        )
+        .accounts({ tokenProgram: TOKEN_PROGRAM_ID })
+        .rpc();
+    });
+
+    it("Comments are stored at increasing indexes", async () => {
+      await program.methods
+        .addComment("First!")
+        .accounts({ movieReview: reviewPda })
+        .rpc();
+      await program.methods
+        .addComment("Agreed, great movie")
+        .accounts({ movieReview: reviewPda })
+        .rpc();
+
+      const counter = await program.account.commentCounter.fetch(counterPda);
+      expect(counter.count.toNumber()).to.equal(2);
+      expect(counter.open.toNumber()).to.equal(2);
+
+      const second = await program.account.comment.fetch(commentPda(1));
+      expect(second.body).to.equal("Agreed, great movie");
+      expect(second.index.toNumber()).to.equal(1);
+      expect(second.review.equals(reviewPda)).to.be.true;
+      expect(second.author.equals(provider.wallet.publicKey)).to.be.true;
+    });
+
+    it("Only the author can delete a comment", async () => {
+      const stranger = anchor.web3.Keypair.generate();
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          stranger.publicKey,
+          anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+
+      try {
+        await program.methods
+          .deleteComment()
+          .accounts({ comment: commentPda(0), author: stranger.publicKey })
+          .signers([stranger])
+          .rpc();
+        expect.fail("Stranger should not be able to delete the comment");
+      } catch (error) {
+        expect(error.error.errorCode.code).to.equal("NotCommentAuthor");
+      }
+
+      await program.methods
+        .deleteComment()
+        .accounts({ comment: commentPda(0) })
+        .rpc();
+
+      const deleted = await provider.connection.getAccountInfo(commentPda(0));
+      expect(deleted).to.be.null;
+
+      // Indexes aren't reused, only the number of open comments goes down
+      const counter = await program.account.commentCounter.fetch(counterPda);
+      expect(counter.count.toNumber()).to.equal(2);
+      expect(counter.open.toNumber()).to.equal(1);
+    });
+  });
+
+  describe("moderation", () => {
+    const reviewTitle = "Controversial movie";
+    const [reviewPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [titleSeed(reviewTitle), provider.wallet.publicKey.toBuffer()],
+      program.programId
+    );
+
+    before(async () => {
+      await program.methods
+        .addMovieReview(
+          reviewTitle,
+          movie.description,
+          movie.rating,
+          movie.genre,
+          movie.releaseYear,
+          movie.tags
+        )
+        .accounts({ tokenProgram: TOKEN_PROGRAM_ID })
+        .rpc();
+      await program.methods.initializeConfig().rpc();
+    });
+
+    it("Anyone can report a review", async () => {
+      await program.methods
+        .reportReview()
+        .accounts({ movieReview: reviewPda })
+        .rpc();
+
+      const account = await program.account.movieReviewData.fetch(reviewPda);
+      expect(account.reportCount).to.equal(1);
+
+      // Each wallet can only report a review once
+      try {
+        await program.methods
+          .reportReview()
+          .accounts({ movieReview: reviewPda })
+          .rpc();
+        expect.fail("Second report should have been rejected");
+      } catch (error) {
+        expect(error.message).to.include("already in use");
+      }
+    });
+
+    it("Only moderators can hide a review", async () => {
+      try {
+        await program.methods
+          .hideReview()
+          .accounts({ movieReview: reviewPda })
+          .rpc();
+        expect.fail("Non-moderator should not be able to hide the review");
+      } catch (error) {
+        expect(error.error.errorCode.code).to.equal("NotModerator");
+      }
+    });
+
+    it("Hidden reviews are excluded from the visible reviews", async () => {
+      await program.methods.addModerator(provider.wallet.publicKey).rpc();
+      await program.methods
+        .hideReview()
+        .accounts({ movieReview: reviewPda })
+        .rpc();
+
+      let visible = await visibleReviews();
+      expect(visible.some((review) => review.publicKey.equals(reviewPda))).to.be.false;
+
+      await program.methods
+        .unhideReview()
+        .accounts({ movieReview: reviewPda })
+        .rpc();
+
+      visible = await visibleReviews();
+      expect(visible.some((review) => review.publicKey.equals(reviewPda))).to.be.true;
+    });
+  });
+
+  describe("likes", () => {
+    const reviewTitle = "Helpful movie";
+    const voter = anchor.web3.Keypair.generate();
+    const [reviewPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [titleSeed(reviewTitle), provider.wallet.publicKey.toBuffer()],
+      program.programId
+    );
+    const [likePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("like"), reviewPda.toBuffer(), voter.publicKey.toBuffer()],
+      program.programId
+    );
+
+    before(async () => {
+      await program.methods
+        .addMovieReview(
+          reviewTitle,
+          movie.description,
+          movie.rating,
+          movie.genre,
+          movie.releaseYear,
+          movie.tags
+        )
+        .accounts({ tokenProgram: TOKEN_PROGRAM_ID })
+        .rpc();
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          voter.publicKey,
+          anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    });
+
+    it("A wallet can like a review only once", async () => {
+      await program.methods
+        .likeReview()
+        .accounts({ movieReview: reviewPda, voter: voter.publicKey })
+        .signers([voter])
+        .rpc();
+
+      const account = await program.account.movieReviewData.fetch(reviewPda);
+      expect(account.likeCount.toNumber()).to.equal(1);
+
+      // The like counts towards the reputation of the review's author
+      const [profilePda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("profile"), provider.wallet.publicKey.toBuffer()],
+        program.programId
+      );
+      const profile = await program.account.reviewerProfile.fetch(profilePda);
+      expect(profile.likesReceived.toNumber()).to.equal(1);
+
+      try {
+        await program.methods
+          .likeReview()
+          .accounts({ movieReview: reviewPda, voter: voter.publicKey })
+          .signers([voter])
+          .rpc();
+        expect.fail("Second like should have been rejected");
+      } catch (error) {
+        expect(error.message).to.include("already in use");
+      }
+    });
+
+    it("Reviewers cannot like their own review", async () => {
+      try {
+        await program.methods
+          .likeReview()
+          .accounts({ movieReview: reviewPda })
+          .rpc();
+        expect.fail("Self-like should have been rejected");
+      } catch (error) {
+        expect(error.error.errorCode.code).to.equal("CannotLikeOwnReview");
+      }
+    });
+
+    it("Unliking closes the like marker", async () => {
+      await program.methods
+        .unlikeReview()
+        .accounts({ movieReview: reviewPda, voter: voter.publicKey })
+        .signers([voter])
+        .rpc();
+
+      const account = await program.account.movieReviewData.fetch(reviewPda);
+      expect(account.likeCount.toNumber()).to.equal(0);
+      expect(await provider.connection.getAccountInfo(likePda)).to.be.null;
+    });
+  });
+
+  describe("profiles", () => {
+    const [profilePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), provider.wallet.publicKey.toBuffer()],
+      program.programId
+    );
+
+    it("Reviewers can set a display name", async () => {
+      await program.methods.updateProfile("Movie buff").rpc();
+
+      const profile = await program.account.reviewerProfile.fetch(profilePda);
+      expect(profile.displayName).to.equal("Movie buff");
+      expect(profile.authority.equals(provider.wallet.publicKey)).to.be.true;
+    });
+
+    it("Reputation follows reviews and likes", async () => {
+      const before = await program.account.reviewerProfile.fetch(profilePda);
+      expect(before.reputation.toNumber()).to.equal(
+        before.reviewCount.toNumber() * 10 + before.likesReceived.toNumber() * 5
+      );
+
+      const title = "Profile movie";
+      await program.methods
+        .addMovieReview(
+          title,
+          movie.description,
+          movie.rating,
+          movie.genre,
+          movie.releaseYear,
+          movie.tags
+        )
+        .accounts({ tokenProgram: TOKEN_PROGRAM_ID })
+        .rpc();
+
+      const after = await program.account.reviewerProfile.fetch(profilePda);
+      expect(after.reviewCount.toNumber()).to.equal(before.reviewCount.toNumber() + 1);
+      expect(after.reputation.toNumber()).to.equal(before.reputation.toNumber() + 10);
+
+      await program.methods.deleteMovieReview(title).rpc();
+
+      const deleted = await program.account.reviewerProfile.fetch(profilePda);
+      expect(deleted.reviewCount.toNumber()).to.equal(before.reviewCount.toNumber());
+    });
+  });
+
+  describe("renaming", () => {
+    const oldTitle = "The Matrx";
+    const newTitle = "The Matrix";
+    const reviewAddress = (title: string) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [titleSeed(title), provider.wallet.publicKey.toBuffer()],
+        program.programId
+      )[0];
+    const addReview = (title: string) =>
+      program.methods
+        .addMovieReview(
+          title,
+          movie.description,
+          movie.rating,
+          movie.genre,
+          movie.releaseYear,
+          movie.tags
+        )
+        .accounts({ tokenProgram: TOKEN_PROGRAM_ID })
+        .rpc();
+
+    it("A review can be moved to a new title", async () => {
+      await addReview(oldTitle);
+      await program.methods.renameMovieReview(oldTitle, newTitle).rpc();
+
+      const renamed = await program.account.movieReviewData.fetch(reviewAddress(newTitle));
+      expect(renamed.title).to.equal(newTitle);
+      expect(renamed.description).to.equal(movie.description);
+      expect(renamed.editCount).to.equal(1);
+      expect(await provider.connection.getAccountInfo(reviewAddress(oldTitle))).to.be.null;
+    });
+
+    it("Renaming onto an existing review is rejected", async () => {
+      await addReview(oldTitle);
+
+      try {
+        await program.methods.renameMovieReview(oldTitle, newTitle).rpc();
+        expect.fail("Rename should have collided with the existing review");
+      } catch (error) {
+        expect(error.message).to.include("already in use");
+      }
+
+      const existing = await program.account.movieReviewData.fetch(reviewAddress(oldTitle));
+      expect(existing.title).to.equal(oldTitle);
+    });
+  });
+
+  it("Updates emit an event with the old and new rating", async () => {
+    const title = "Evented movie";
+    await program.methods
+      .addMovieReview(
+        title,
+        movie.description,
+        movie.rating,
+        movie.genre,
+        movie.releaseYear,
+        movie.tags
+      )
+      .accounts({ tokenProgram: TOKEN_PROGRAM_ID })
+      .rpc();
+
+    const signature = await program.methods
+      .updateMovieReview(
+        title,
+        movie.description,
+        3,
+        movie.genre,
+        movie.releaseYear,
+        movie.tags
+      )
+      .rpc({ commitment: "confirmed" });
+
+    // Events are read back from the transaction logs with the program IDL
+    const transaction = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const parser = new anchor.EventParser(program.programId, program.coder);
+    const events = [...parser.parseLogs(transaction.meta.logMessages)];
+
+    const updated = events.find((event) => event.name === "reviewUpdated");
+    expect(updated.data.title).to.equal(title);
+    expect(updated.data.oldRating).to.equal(movie.rating);
+    expect(updated.data.newRating).to.equal(3);
+
+    await program.methods.deleteMovieReview(title).rpc();
   });
-});
\ No newline at end of file
+});